@@ -0,0 +1,372 @@
+// Calendar durations, as newtypes so a number of days can't be passed
+// where a number of years is expected.
+//
+// Years and months have no fixed length in days: 2024 has 366 of them,
+// February 2023 has 28. So converting between the two families takes a
+// start date, and `Years(1).to_days(start)` is 365 or 366 depending on
+// `start`. Within a family the conversions are exact: a year is 12 months
+// and a week is 7 days.
+//
+// Adding a month to Jan 31 lands on the last day of February, and adding
+// a year to Feb 29 lands on Feb 28, as with `chrono`'s month arithmetic.
+
+use chrono::{Datelike, NaiveDate};
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Years(pub i64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Months(pub i64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Weeks(pub i64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Days(pub i64);
+
+// Any mix of the above, e.g. "5y 3m 2d". Years and months are kept as
+// months, weeks as days; the two can't be combined without a date.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Period {
+    pub months: i64,
+    pub days: i64,
+}
+
+// `date + months`, or `None` if that's out of `NaiveDate`'s range
+fn add_months(date: NaiveDate, months: i64) -> Option<NaiveDate> {
+    let count = chrono::Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+    if months >= 0 {
+        date.checked_add_months(count)
+    } else {
+        date.checked_sub_months(count)
+    }
+}
+
+fn add_days(date: NaiveDate, days: i64) -> Option<NaiveDate> {
+    date.checked_add_signed(chrono::TimeDelta::try_days(days)?)
+}
+
+fn days_between(from: NaiveDate, to: NaiveDate) -> Days {
+    Days(to.signed_duration_since(from).num_days())
+}
+
+impl Years {
+    // The number of days from `start` to the same date `self` years later
+    pub fn to_days(self, start: NaiveDate) -> Option<Days> {
+        Months::from(self).to_days(start)
+    }
+}
+
+impl Months {
+    pub fn to_days(self, start: NaiveDate) -> Option<Days> {
+        add_months(start, self.0).map(|end| days_between(start, end))
+    }
+}
+
+impl Days {
+    // The whole years in `self` days from `start`, rounded towards zero
+    pub fn to_years(self, start: NaiveDate) -> Option<Years> {
+        let months = self.to_months(start)?;
+        Some(Years(months.0 / 12))
+    }
+
+    // The whole months in `self` days from `start`, rounded towards zero
+    pub fn to_months(self, start: NaiveDate) -> Option<Months> {
+        let end = add_days(start, self.0)?;
+        Some(Months(Period::between(start, end).months))
+    }
+}
+
+impl From<Years> for Months {
+    fn from(years: Years) -> Months {
+        Months(years.0 * 12)
+    }
+}
+
+impl From<Weeks> for Days {
+    fn from(weeks: Weeks) -> Days {
+        Days(weeks.0 * 7)
+    }
+}
+
+impl From<Years> for Period {
+    fn from(years: Years) -> Period {
+        Period::from(Months::from(years))
+    }
+}
+
+impl From<Months> for Period {
+    fn from(months: Months) -> Period {
+        Period {
+            months: months.0,
+            days: 0,
+        }
+    }
+}
+
+impl From<Weeks> for Period {
+    fn from(weeks: Weeks) -> Period {
+        Period::from(Days::from(weeks))
+    }
+}
+
+impl From<Days> for Period {
+    fn from(days: Days) -> Period {
+        Period {
+            months: 0,
+            days: days.0,
+        }
+    }
+}
+
+// `+`, `-`, unary `-` and `* i64` within each type
+macro_rules! arithmetic {
+    ($($ty:ident),+) => {$(
+        impl Add for $ty {
+            type Output = $ty;
+
+            fn add(self, other: $ty) -> $ty {
+                $ty(self.0 + other.0)
+            }
+        }
+
+        impl Sub for $ty {
+            type Output = $ty;
+
+            fn sub(self, other: $ty) -> $ty {
+                $ty(self.0 - other.0)
+            }
+        }
+
+        impl Neg for $ty {
+            type Output = $ty;
+
+            fn neg(self) -> $ty {
+                $ty(-self.0)
+            }
+        }
+
+        impl Mul<i64> for $ty {
+            type Output = $ty;
+
+            fn mul(self, factor: i64) -> $ty {
+                $ty(self.0 * factor)
+            }
+        }
+    )+};
+}
+
+arithmetic!(Years, Months, Weeks, Days);
+
+// `$lhs + $rhs` and `$lhs - $rhs` in the smaller unit, `$output`, which
+// both convert to
+macro_rules! mixed_arithmetic {
+    ($($lhs:ident + $rhs:ident => $output:ident),+ $(,)?) => {$(
+        impl Add<$rhs> for $lhs {
+            type Output = $output;
+
+            fn add(self, other: $rhs) -> $output {
+                $output::from(self) + $output::from(other)
+            }
+        }
+
+        impl Sub<$rhs> for $lhs {
+            type Output = $output;
+
+            fn sub(self, other: $rhs) -> $output {
+                $output::from(self) - $output::from(other)
+            }
+        }
+    )+};
+}
+
+mixed_arithmetic!(
+    Years + Months => Months,
+    Months + Years => Months,
+    Weeks + Days => Days,
+    Days + Weeks => Days,
+    Years + Weeks => Period,
+    Years + Days => Period,
+    Months + Weeks => Period,
+    Months + Days => Period,
+    Weeks + Years => Period,
+    Weeks + Months => Period,
+    Days + Years => Period,
+    Days + Months => Period,
+);
+
+impl<T: Into<Period>> Add<T> for Period {
+    type Output = Period;
+
+    fn add(self, other: T) -> Period {
+        let other = other.into();
+        Period {
+            months: self.months + other.months,
+            days: self.days + other.days,
+        }
+    }
+}
+
+impl<T: Into<Period>> Sub<T> for Period {
+    type Output = Period;
+
+    fn sub(self, other: T) -> Period {
+        self + -other.into()
+    }
+}
+
+impl Neg for Period {
+    type Output = Period;
+
+    fn neg(self) -> Period {
+        Period {
+            months: -self.months,
+            days: -self.days,
+        }
+    }
+}
+
+impl Period {
+    // The years, months and days from `from` to `to`, with as many whole
+    // months as fit. Negative if `to` comes first.
+    pub fn between(from: NaiveDate, to: NaiveDate) -> Period {
+        if to < from {
+            return -Period::between(to, from);
+        }
+        let mut months =
+            (to.year() - from.year()) as i64 * 12 + to.month() as i64 - from.month() as i64;
+        // The estimate is one too many if `to`'s day of the month is
+        // earlier than `from`'s. Both dates are real, so this can't fail.
+        let mut start = add_months(from, months).expect("month between two dates");
+        if start > to {
+            months -= 1;
+            start = add_months(from, months).expect("month between two dates");
+        }
+        Period {
+            months,
+            days: days_between(start, to).0,
+        }
+    }
+
+    // `date` moved by the months first, then by the days
+    pub fn add_to(self, date: NaiveDate) -> Option<NaiveDate> {
+        add_days(add_months(date, self.months)?, self.days)
+    }
+
+    pub fn to_days(self, start: NaiveDate) -> Option<Days> {
+        self.add_to(start).map(|end| days_between(start, end))
+    }
+}
+
+impl fmt::Display for Period {
+    // Parses back to the same `Period`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts = [
+            (self.months / 12, 'y'),
+            (self.months % 12, 'm'),
+            (self.days, 'd'),
+        ];
+        let mut parts = parts.iter().filter(|(n, _)| *n != 0).peekable();
+        if parts.peek().is_none() {
+            return write!(f, "0d");
+        }
+        for (i, (n, unit)) in parts.enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}{}", n, unit)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    // A unit without a number in front, like "y"
+    MissingNumber(usize),
+    // A number without a unit after it, like "5"
+    MissingUnit(usize),
+    UnknownUnit(char),
+    TooLarge,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty duration"),
+            ParseError::MissingNumber(at) => write!(f, "expected a number at byte {}", at),
+            ParseError::MissingUnit(at) => {
+                write!(f, "expected one of `y`, `m`, `w` or `d` at byte {}", at)
+            }
+            ParseError::UnknownUnit(c) => {
+                write!(f, "unknown unit `{}`, expected `y`, `m`, `w` or `d`", c)
+            }
+            ParseError::TooLarge => write!(f, "duration too large"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for Period {
+    type Err = ParseError;
+
+    // A number and a unit, any number of times: "5y 3m 2d", "2w", "-1m",
+    // "1y1y". Spaces between the parts are optional.
+    fn from_str(s: &str) -> Result<Period, ParseError> {
+        let mut period = Period::default();
+        let mut rest = s.trim_start();
+        if rest.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        while !rest.is_empty() {
+            let at = s.len() - rest.len();
+            let sign_len = usize::from(rest.starts_with('-'));
+            let digits = rest[sign_len..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len() - sign_len);
+            if digits == 0 {
+                return Err(ParseError::MissingNumber(at));
+            }
+            let (number, after) = rest.split_at(sign_len + digits);
+            let n: i64 = number.parse().map_err(|_| ParseError::TooLarge)?;
+
+            let unit = after
+                .chars()
+                .next()
+                .ok_or(ParseError::MissingUnit(s.len()))?;
+            let part = match unit {
+                'y' => Period::from(Months(n.checked_mul(12).ok_or(ParseError::TooLarge)?)),
+                'm' => Period::from(Months(n)),
+                'w' => Period::from(Days(n.checked_mul(7).ok_or(ParseError::TooLarge)?)),
+                'd' => Period::from(Days(n)),
+                c if c.is_whitespace() => {
+                    return Err(ParseError::MissingUnit(s.len() - after.len()))
+                }
+                c => return Err(ParseError::UnknownUnit(c)),
+            };
+            period = Period {
+                months: period
+                    .months
+                    .checked_add(part.months)
+                    .ok_or(ParseError::TooLarge)?,
+                days: period
+                    .days
+                    .checked_add(part.days)
+                    .ok_or(ParseError::TooLarge)?,
+            };
+            rest = after[unit.len_utf8()..].trim_start();
+        }
+        Ok(period)
+    }
+}
+
+// Whole years from `birth` to `on`. Someone born on Feb 29 gets a year
+// older on Feb 28 in common years.
+pub fn age(birth: NaiveDate, on: NaiveDate) -> Years {
+    Years(Period::between(birth, on).months / 12)
+}
@@ -4,38 +4,80 @@
 // For example, an age verification function that checks age in years, *must* be given a
 // value of type `Years`.
 
-#[derive(Debug)]
-struct Years(i64);
+mod calendar;
 
-#[derive(Debug)]
-struct Days(i64);
-
-impl Years {
-    pub fn to_days(&self) -> Days {
-        Days(self.0 * 365)
-    }
-}
-
-impl Days {
-    pub fn to_years(&self) -> Years {
-        Years(self.0 / 365)
-    }
-}
+use calendar::{Days, Months, Period, Weeks, Years};
+use chrono::NaiveDate;
 
 fn old_enough(age: &Years) -> bool {
     age.0 >= 18
 }
 
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).expect("valid date")
+}
+
 fn main() {
+    // How many days a year has depends on which year it is
     let age = Years(5);
-    let age_days = age.to_days();
-    println!("{:?} years to days: {:?}", age, age_days);
+    for start in [date(2021, 3, 1), date(2023, 3, 1)] {
+        let age_days = age.to_days(start).expect("date in range");
+        println!(
+            "{:?} from {} to days: {:?}, and back: {:?}",
+            age,
+            start,
+            age_days,
+            age_days.to_years(start)
+        );
+    }
     println!("Old enough {}", old_enough(&age));
-    println!("Old enough {}", old_enough(&age_days.to_years()));
+
+    // Ages come from dates, not from counting days
+    let birth = date(2004, 2, 29);
+    for on in [date(2022, 2, 27), date(2022, 2, 28), date(2024, 2, 29)] {
+        let age = calendar::age(birth, on);
+        println!(
+            "Born {}, on {}: {:?}, {}, old enough {}",
+            birth,
+            on,
+            age,
+            Period::between(birth, on),
+            old_enough(&age)
+        );
+    }
+
+    // Durations parse from strings and print the same way
+    for input in ["5y 3m 2d", "2w", "1y -1d", "18m", "5", "3x"] {
+        match input.parse::<Period>() {
+            Ok(period) => {
+                let start = date(2024, 1, 31);
+                println!(
+                    "{:?} -> {}, from {} to {}, {:?}",
+                    input,
+                    period,
+                    start,
+                    period.add_to(start).expect("date in range"),
+                    period.to_days(start).expect("date in range")
+                )
+            }
+            Err(e) => println!("{:?} -> {}", input, e),
+        }
+    }
+
+    // Years and months add up exactly, as do weeks and days, and any mix
+    // of the two makes a `Period`
+    let months: Months = Years(1) + Months(6);
+    let days: Days = Weeks(2) - Days(3);
+    let period: Period = Years(5) + Days(2) + Months(3);
+    println!("{:?}, {:?}, {}", months, days, period);
+    assert_eq!(period, "5y 3m 2d".parse().unwrap());
 
     // To obtain the `newtype`'s value as the base type, you may use the tuple of
     // destructuring syntax like so:
     let years = Years(42);
+    // Both are only there to show the syntax, and never used
+    #[allow(unused_variables)]
     let years_as_primitive_1: i64 = years.0; // Tuple
+    #[allow(unused_variables)]
     let Years(years_as_primitive_1) = years; // Destructuring
 }
@@ -1,31 +1,9 @@
 // A useful method of unit conversions can be examined by implementing Add with a phantom type parameter.
-// The Add trait is examined below:
+// `Length`, its units and the `Add` trait are in `units.rs`, examined below:
+mod units;
+
 use std::marker::PhantomData;
-use std::ops::Add;
-
-/// Create void enumerations to define unit types.
-#[derive(Debug, Clone, Copy)]
-enum Inch {}
-#[derive(Debug, Clone, Copy)]
-enum Mm {}
-
-/// `Length` is a type with phantom type parameter `Unit`,
-/// and is not generic over the length type (that is `f64`).
-///
-/// `f64` already implements the `Clone` and `Copy` traits.
-#[derive(Debug, Clone, Copy)]
-struct Length<Unit>(f64, PhantomData<Unit>);
-
-/// The `Add` trait defines the behavior of the `+` operator.
-impl<Unit> Add for Length<Unit> {
-    type Output = Length<Unit>;
-
-    // add() returns a new `Length` struct containing the sum.
-    fn add(self, rhs: Length<Unit>) -> Length<Unit> {
-        // `+` calls the `Add` implementation for `f64`.
-        Length(self.0 + rhs.0, PhantomData)
-    }
-}
+use units::{Area, Cm, Foot, Hour, Inch, Km, Length, Mile, Minute, Mm, Speed, Time, Yard, M};
 
 fn main() {
     // Specifies `one_foot` to have phantom type parameter `Inch`.
@@ -33,7 +11,7 @@ fn main() {
     // `one_meter` has phantom type parameter `Mm`.
     let one_meter: Length<Mm> = Length(1000.0, PhantomData);
 
-    // `+` calls the `add()` method we implemented for `Length<Unit>`.
+    // `+` calls the `add()` method implemented for `Length<Unit>`.
     //
     // Since `Length` implements `Copy`, `add()` does not consume
     // `one_foot` and `one_meter` but copies them into `self` and `rhs`.
@@ -41,10 +19,56 @@ fn main() {
     let two_meters = one_meter + one_meter;
 
     // Addition works.
-    println!("one foot + one_foot = {:?} in", two_feet.0);
-    println!("one meter + one_meter = {:?} mm", two_meters.0);
+    println!("one foot + one_foot = {}", two_feet);
+    println!("one meter + one_meter = {}", two_meters);
 
     // Nonsensical operations fail as they should:
     // Compile-time Error: type mismatch.
     //let one_feter = one_foot + one_meter;
+
+    // Converting first makes it work, and says so in the code
+    let one_feter: Length<Mm> = one_foot.to::<Mm>() + one_meter;
+    println!("one foot in mm + one meter = {}", one_feter);
+    println!("and back in inches: {:.3}", one_feter.to::<Inch>());
+    assert_eq!(one_foot.to::<Mm>(), Length::new(304.8));
+
+    // Lengths multiply into areas, in the same unit
+    let sheet: Area<Mm> = Length::<Mm>::new(210.0) * Length::new(297.0);
+    println!("An A4 sheet is {}, or {:.1}", sheet, sheet.to::<Inch>());
+
+    // and divide by time into speeds
+    let walk: Speed<Km, Hour> = Length::<Km>::new(5.0) / Time::<Hour>::new(1.0);
+    println!(
+        "Walking at {} is {:.2} or {:.1}",
+        walk,
+        walk.to::<M, units::Second>(),
+        walk.to::<Mm, Minute>()
+    );
+    let duration: Time<Minute> = "90 min".parse().expect("a time in minutes");
+    println!(
+        "In {} ({}) that's {}",
+        duration,
+        duration.to::<Hour>(),
+        walk * duration.to::<Hour>()
+    );
+
+    let mile = Length::<Mile>::new(1.0);
+    println!(
+        "A mile is {}, {}, {} or {}",
+        mile.to::<Yard>(),
+        mile.to::<Foot>(),
+        mile.to::<Inch>(),
+        mile.to::<Km>()
+    );
+
+    // Parsing checks the unit too
+    for input in ["12 in", "0.5in", "30 cm", "12", "twelve in"] {
+        match input.parse::<Length<Inch>>() {
+            Ok(length) => println!("{:?} -> {} ({})", input, length, length.to::<Mm>()),
+            Err(e) => println!("{:?} -> {}", input, e),
+        }
+    }
+    // A different unit is parsed as that unit, and then converted
+    let a4_width: Length<Cm> = "21 cm".parse().expect("a length in cm");
+    println!("{} is {:.2}", a4_width, a4_width.to::<Inch>());
 }
@@ -0,0 +1,307 @@
+// Quantities tagged with their unit at compile time. The unit is a phantom
+// type parameter, so it costs nothing at runtime, but `Length<Inch>` and
+// `Length<Mm>` are different types and can't be mixed up.
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Add, Div, Mul, Sub};
+use std::str::FromStr;
+
+/// A unit of length, as a whole number of micrometres, so the ratio
+/// between two units is an exact fraction rather than a rounded factor
+/// like 0.03937 inches per millimetre.
+pub trait LengthUnit: Copy {
+    const SUFFIX: &'static str;
+    const MICROMETRES: u64;
+}
+
+/// A unit of time, as an exact whole number of seconds.
+pub trait TimeUnit: Copy {
+    const SUFFIX: &'static str;
+    const SECONDS: u64;
+}
+
+/// Declares void enumerations as units.
+macro_rules! units {
+    ($unit_trait:ident, $factor:ident: $($unit:ident = $suffix:literal, $value:expr;)+) => {$(
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+        pub enum $unit {}
+
+        impl $unit_trait for $unit {
+            const SUFFIX: &'static str = $suffix;
+            const $factor: u64 = $value;
+        }
+    )+};
+}
+
+units!(LengthUnit, MICROMETRES:
+    Mm = "mm", 1_000;
+    Cm = "cm", 10_000;
+    M = "m", 1_000_000;
+    Km = "km", 1_000_000_000;
+    Inch = "in", 25_400;
+    Foot = "ft", 304_800;
+    Yard = "yd", 914_400;
+    Mile = "mi", 1_609_344_000;
+);
+
+units!(TimeUnit, SECONDS:
+    Second = "s", 1;
+    Minute = "min", 60;
+    Hour = "h", 3_600;
+);
+
+/// The ratio `from / to` in lowest terms, as a numerator and denominator.
+fn ratio(from: u64, to: u64) -> (u64, u64) {
+    let (mut a, mut b) = (from, to);
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    (from / a, to / a)
+}
+
+/// `value * num / den`, rounded once instead of after both the
+/// multiplication and the division. `num` and `den` must be below 2^53,
+/// where they are exact in an `f64`.
+///
+/// An `f64` can't hold every result, 1 in is 1/36 yd, so converting can
+/// still lose what lies below the last bit. It just never loses more.
+fn convert(value: f64, (num, den): (u64, u64)) -> f64 {
+    let (num, den) = (num as f64, den as f64);
+    // The exact product is `product + error`
+    let product = value * num;
+    let error = value.mul_add(num, -product);
+    // and `product` is exactly `quotient * den + remainder`
+    let quotient = product / den;
+    let remainder = (-quotient).mul_add(den, product);
+    quotient + (remainder + error) / den
+}
+
+/// `Length` is a type with phantom type parameter `Unit`,
+/// and is not generic over the length type (that is `f64`).
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Length<Unit>(pub f64, pub PhantomData<Unit>);
+
+/// The product of two lengths in the same unit.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Area<Unit>(pub f64, pub PhantomData<Unit>);
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Time<Unit>(pub f64, pub PhantomData<Unit>);
+
+/// A length divided by a time, e.g. `Speed<Km, Hour>`.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Speed<L, T>(pub f64, pub PhantomData<(L, T)>);
+
+impl<U: LengthUnit> Length<U> {
+    pub fn new(value: f64) -> Length<U> {
+        Length(value, PhantomData)
+    }
+
+    /// The same length in another unit. Always explicit: nothing converts
+    /// on its own, and `+` still needs both sides in the same unit.
+    pub fn to<V: LengthUnit>(self) -> Length<V> {
+        Length::new(convert(self.0, ratio(U::MICROMETRES, V::MICROMETRES)))
+    }
+}
+
+impl<U: LengthUnit> Area<U> {
+    pub fn new(value: f64) -> Area<U> {
+        Area(value, PhantomData)
+    }
+
+    pub fn to<V: LengthUnit>(self) -> Area<V> {
+        let (num, den) = ratio(U::MICROMETRES, V::MICROMETRES);
+        Area::new(convert(self.0, (num * num, den * den)))
+    }
+}
+
+impl<U: TimeUnit> Time<U> {
+    pub fn new(value: f64) -> Time<U> {
+        Time(value, PhantomData)
+    }
+
+    pub fn to<V: TimeUnit>(self) -> Time<V> {
+        Time::new(convert(self.0, ratio(U::SECONDS, V::SECONDS)))
+    }
+}
+
+impl<L: LengthUnit, T: TimeUnit> Speed<L, T> {
+    pub fn new(value: f64) -> Speed<L, T> {
+        Speed(value, PhantomData)
+    }
+
+    /// e.g. `Speed<M, Second>` to `Speed<Km, Hour>`
+    pub fn to<L2: LengthUnit, T2: TimeUnit>(self) -> Speed<L2, T2> {
+        let (length_num, length_den) = ratio(L::MICROMETRES, L2::MICROMETRES);
+        // Per second to per hour is times 3600
+        let (time_num, time_den) = ratio(T2::SECONDS, T::SECONDS);
+        let both = ratio(length_num * time_num, length_den * time_den);
+        Speed::new(convert(self.0, both))
+    }
+}
+
+/// `+` and `-` between two quantities of the same type. The unit is part
+/// of the type, so `Length<Inch> + Length<Mm>` doesn't compile.
+macro_rules! same_unit_arithmetic {
+    ($($quantity:ident<$($param:ident: $bound:ident),+>),+) => {$(
+        impl<$($param: $bound),+> Add for $quantity<$($param),+> {
+            type Output = $quantity<$($param),+>;
+
+            fn add(self, rhs: $quantity<$($param),+>) -> $quantity<$($param),+> {
+                $quantity::new(self.0 + rhs.0)
+            }
+        }
+
+        impl<$($param: $bound),+> Sub for $quantity<$($param),+> {
+            type Output = $quantity<$($param),+>;
+
+            fn sub(self, rhs: $quantity<$($param),+>) -> $quantity<$($param),+> {
+                $quantity::new(self.0 - rhs.0)
+            }
+        }
+
+        impl<$($param: $bound),+> Mul<f64> for $quantity<$($param),+> {
+            type Output = $quantity<$($param),+>;
+
+            fn mul(self, factor: f64) -> $quantity<$($param),+> {
+                $quantity::new(self.0 * factor)
+            }
+        }
+    )+};
+}
+
+same_unit_arithmetic!(
+    Length<U: LengthUnit>,
+    Area<U: LengthUnit>,
+    Time<U: TimeUnit>,
+    Speed<L: LengthUnit, T: TimeUnit>
+);
+
+impl<U: LengthUnit> Mul for Length<U> {
+    type Output = Area<U>;
+
+    fn mul(self, rhs: Length<U>) -> Area<U> {
+        Area::new(self.0 * rhs.0)
+    }
+}
+
+impl<U: LengthUnit> Div<Length<U>> for Area<U> {
+    type Output = Length<U>;
+
+    fn div(self, rhs: Length<U>) -> Length<U> {
+        Length::new(self.0 / rhs.0)
+    }
+}
+
+impl<L: LengthUnit, T: TimeUnit> Div<Time<T>> for Length<L> {
+    type Output = Speed<L, T>;
+
+    fn div(self, rhs: Time<T>) -> Speed<L, T> {
+        Speed::new(self.0 / rhs.0)
+    }
+}
+
+impl<L: LengthUnit, T: TimeUnit> Mul<Time<T>> for Speed<L, T> {
+    type Output = Length<L>;
+
+    fn mul(self, rhs: Time<T>) -> Length<L> {
+        Length::new(self.0 * rhs.0)
+    }
+}
+
+/// Writes the value, formatted as asked, e.g. with `{:.1}`, and then the
+/// unit.
+impl<U: LengthUnit> fmt::Display for Length<U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)?;
+        write!(f, " {}", U::SUFFIX)
+    }
+}
+
+impl<U: LengthUnit> fmt::Display for Area<U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)?;
+        write!(f, " {}²", U::SUFFIX)
+    }
+}
+
+impl<U: TimeUnit> fmt::Display for Time<U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)?;
+        write!(f, " {}", U::SUFFIX)
+    }
+}
+
+impl<L: LengthUnit, T: TimeUnit> fmt::Display for Speed<L, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)?;
+        write!(f, " {}/{}", L::SUFFIX, T::SUFFIX)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidNumber(String),
+    MissingUnit,
+    /// The text names another unit than the type, like "12 mm" for a
+    /// `Length<Inch>`. Convert with `to` after parsing instead.
+    WrongUnit {
+        expected: &'static str,
+        found: String,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::InvalidNumber(s) => write!(f, "invalid number {:?}", s),
+            ParseError::MissingUnit => write!(f, "missing unit"),
+            ParseError::WrongUnit { expected, found } => {
+                write!(f, "expected unit `{}`, found `{}`", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Splits "12 in", "12in" or "-1.5e3 mm" into the number and the unit.
+fn parse_quantity(s: &str, expected: &'static str) -> Result<f64, ParseError> {
+    let s = s.trim();
+    let unit_start = s
+        .rfind(|c: char| c.is_ascii_digit() || c == '.' || c.is_whitespace())
+        .map_or(0, |i| i + 1);
+    let (number, unit) = s.split_at(unit_start);
+    if unit.is_empty() {
+        return Err(ParseError::MissingUnit);
+    }
+    if unit != expected {
+        return Err(ParseError::WrongUnit {
+            expected,
+            found: unit.to_string(),
+        });
+    }
+    let number = number.trim_end();
+    // `f64` also parses "inf" and "NaN", which aren't lengths or times
+    number
+        .parse()
+        .ok()
+        .filter(|n: &f64| n.is_finite())
+        .ok_or_else(|| ParseError::InvalidNumber(number.to_string()))
+}
+
+impl<U: LengthUnit> FromStr for Length<U> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Length<U>, ParseError> {
+        parse_quantity(s, U::SUFFIX).map(Length::new)
+    }
+}
+
+impl<U: TimeUnit> FromStr for Time<U> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Time<U>, ParseError> {
+        parse_quantity(s, U::SUFFIX).map(Time::new)
+    }
+}
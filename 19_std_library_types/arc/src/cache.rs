@@ -0,0 +1,232 @@
+// A thread-safe cache with per-entry expiry and LRU eviction.
+//
+// `Cache` is a cheap handle around `Arc<Inner>`: clone it into as many
+// threads as needed. Lookups only take the read side of the `RwLock`.
+// Recency is tracked in an atomic per entry, so even a hit doesn't need
+// the write lock.
+//
+// Each entry holds its value in an `Arc<OnceLock<V>>`. `get_or_insert_with`
+// puts an empty cell in the map and fills it outside of the lock. Threads
+// asking for the same key meanwhile find that cell and wait on it, so the
+// value is computed once, and the rest of the cache stays available while
+// it is.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+struct Entry<V> {
+    value: Arc<OnceLock<V>>,
+    // `None` never expires
+    expires_at: Option<Instant>,
+    // Value of the cache's clock at the last access, for LRU eviction
+    last_used: AtomicU64,
+}
+
+impl<V> Entry<V> {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+struct Inner<K, V> {
+    entries: RwLock<HashMap<K, Entry<V>>>,
+    capacity: usize,
+    ttl: Option<Duration>,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub hits: u64,
+    pub misses: u64,
+    // Expired entries are dropped lazily and count until they are
+    pub len: usize,
+}
+
+pub struct Cache<K, V> {
+    inner: Arc<Inner<K, V>>,
+}
+
+// Implemented by hand, `derive` would require `K: Clone` and `V: Clone`
+impl<K, V> Clone for Cache<K, V> {
+    fn clone(&self) -> Self {
+        Cache {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    // Holds at most `capacity` entries, each expiring `ttl` after it was
+    // inserted unless `ttl` is `None`
+    pub fn new(capacity: usize, ttl: Option<Duration>) -> Cache<K, V> {
+        assert!(capacity > 0, "a cache needs room for at least one entry");
+
+        Cache {
+            inner: Arc::new(Inner {
+                entries: RwLock::new(HashMap::with_capacity(capacity)),
+                capacity,
+                ttl,
+                clock: AtomicU64::new(0),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let entries = self.inner.entries.read().unwrap();
+        let value = entries
+            .get(key)
+            .filter(|entry| !entry.is_expired(Instant::now()))
+            .and_then(|entry| {
+                // An entry whose value is still being computed is a miss
+                let value = entry.value.get()?.clone();
+                self.touch(entry);
+                Some(value)
+            });
+
+        self.count(value.is_some());
+        value
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        self.insert_with_ttl(key, value, self.inner.ttl);
+    }
+
+    // Overrides the cache's default time to live for this entry
+    pub fn insert_with_ttl(&self, key: K, value: V, ttl: Option<Duration>) {
+        let cell = OnceLock::new();
+        let _ = cell.set(value);
+
+        let mut entries = self.inner.entries.write().unwrap();
+        let entry = self.new_entry(Arc::new(cell), ttl);
+        if !entries.contains_key(&key) {
+            self.make_room(&mut entries);
+        }
+        entries.insert(key, entry);
+    }
+
+    // Returns the cached value for `key`, or computes, caches and returns
+    // it. Concurrent calls for the same missing key run `f` only once.
+    pub fn get_or_insert_with<F>(&self, key: K, f: F) -> V
+    where
+        F: FnOnce() -> V,
+    {
+        let now = Instant::now();
+
+        let cell = {
+            let entries = self.inner.entries.read().unwrap();
+            entries
+                .get(&key)
+                .filter(|entry| !entry.is_expired(now))
+                .map(|entry| {
+                    self.touch(entry);
+                    Arc::clone(&entry.value)
+                })
+        };
+
+        let cell = match cell {
+            Some(cell) => {
+                // Waiting on a value another thread is computing counts as
+                // a hit: we didn't have to compute it
+                self.count(true);
+                cell
+            }
+            None => {
+                let mut entries = self.inner.entries.write().unwrap();
+                // Someone may have added the key between the two locks
+                match entries.get(&key).filter(|entry| !entry.is_expired(now)) {
+                    Some(entry) => {
+                        self.touch(entry);
+                        self.count(true);
+                        Arc::clone(&entry.value)
+                    }
+                    None => {
+                        self.count(false);
+                        let entry = self.new_entry(Arc::new(OnceLock::new()), self.inner.ttl);
+                        let cell = Arc::clone(&entry.value);
+                        if !entries.contains_key(&key) {
+                            self.make_room(&mut entries);
+                        }
+                        entries.insert(key, entry);
+                        cell
+                    }
+                }
+            }
+        };
+
+        // Runs `f` in the thread that created the cell, and blocks everyone
+        // else who got hold of it until the value is there
+        cell.get_or_init(f).clone()
+    }
+
+    pub fn remove(&self, key: &K) -> Option<V> {
+        let entry = self.inner.entries.write().unwrap().remove(key)?;
+        entry.value.get().cloned()
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            hits: self.inner.hits.load(Ordering::Relaxed),
+            misses: self.inner.misses.load(Ordering::Relaxed),
+            len: self.inner.entries.read().unwrap().len(),
+        }
+    }
+
+    fn new_entry(&self, value: Arc<OnceLock<V>>, ttl: Option<Duration>) -> Entry<V> {
+        Entry {
+            value,
+            expires_at: ttl.map(|ttl| Instant::now() + ttl),
+            last_used: AtomicU64::new(self.tick()),
+        }
+    }
+
+    // Drops expired entries and, if the cache is still full, the least
+    // recently used one. Finding it is a linear scan, which keeps lookups
+    // free of any bookkeeping beyond one atomic store.
+    fn make_room(&self, entries: &mut HashMap<K, Entry<V>>) {
+        if entries.len() < self.inner.capacity {
+            return;
+        }
+
+        let now = Instant::now();
+        entries.retain(|_, entry| !entry.is_expired(now));
+
+        if entries.len() >= self.inner.capacity {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+                .map(|(key, _)| key.clone());
+            if let Some(key) = oldest {
+                entries.remove(&key);
+            }
+        }
+    }
+
+    fn touch(&self, entry: &Entry<V>) {
+        entry.last_used.store(self.tick(), Ordering::Relaxed);
+    }
+
+    fn tick(&self) -> u64 {
+        self.inner.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn count(&self, hit: bool) {
+        let counter = if hit {
+            &self.inner.hits
+        } else {
+            &self.inner.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -1,3 +1,7 @@
+mod cache;
+
+use cache::Cache;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
@@ -5,19 +9,72 @@ use std::time::Duration;
 fn main() {
     // This variable declaration is where its value is specified.
     let apple = Arc::new("the same apple");
+    let mut children = Vec::new();
 
     for _ in 0..10 {
         // Here there is no value specification as it is a pointer to a
         // reference in the memory heap.
         let apple = Arc::clone(&apple);
 
-        thread::spawn(move || {
+        children.push(thread::spawn(move || {
             // As Arc was used, threads can be spawned using the value allocated
             // in the Arc variable pointer's location.
             println!("{:?}", apple);
-        });
+        }));
     }
 
     // Make sure all Arc instances are printed from spawned threads.
-    thread::sleep(Duration::from_secs(1));
+    for child in children {
+        child.join().unwrap();
+    }
+
+    // A `Cache` is an `Arc` underneath too, every clone shares the entries
+    let cache: Cache<&str, String> = Cache::new(2, Some(Duration::from_millis(200)));
+    let computed = Arc::new(AtomicUsize::new(0));
+
+    // Ten threads ask for the same missing key at once, but the expensive
+    // value is only computed by one of them
+    let children: Vec<_> = (0..10)
+        .map(|_| {
+            let cache = cache.clone();
+            let computed = Arc::clone(&computed);
+            thread::spawn(move || {
+                cache.get_or_insert_with("apple", || {
+                    computed.fetch_add(1, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(50));
+                    "a freshly picked apple".to_string()
+                })
+            })
+        })
+        .collect();
+    for child in children {
+        println!("{}", child.join().unwrap());
+    }
+    println!(
+        "computed {} time(s), {:?}",
+        computed.load(Ordering::SeqCst),
+        cache.stats()
+    );
+
+    // At capacity, the least recently used entry makes room
+    cache.insert("banana", "a banana".to_string());
+    cache.get(&"apple");
+    cache.insert("cherry", "a cherry".to_string());
+    println!(
+        "apple: {:?}, banana: {:?}, cherry: {:?}",
+        cache.get(&"apple"),
+        cache.get(&"banana"),
+        cache.get(&"cherry")
+    );
+
+    // Entries expire after their time to live, which can be set per entry
+    cache.insert_with_ttl("durian", "a durian".to_string(), None);
+    thread::sleep(Duration::from_millis(250));
+    println!(
+        "after 250ms, cherry: {:?}, durian: {:?}",
+        cache.get(&"cherry"),
+        cache.get(&"durian")
+    );
+    println!("removed durian: {:?}", cache.remove(&"durian"));
+    println!("{:?}", cache.stats());
 }
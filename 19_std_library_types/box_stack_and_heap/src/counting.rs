@@ -0,0 +1,333 @@
+// A global allocator that counts what goes through it, so the heap cost of
+// a value can be measured instead of guessed.
+//
+// It's opt-in: nothing is counted unless a binary installs it with
+//
+//     #[global_allocator]
+//     static ALLOCATOR: Counting<System> = Counting::new(System);
+//
+// Two sets of counters are kept. `measure` counts per thread: it sees the
+// allocations of the thread it runs on, even while tests run in parallel,
+// and misses those of any thread `f` starts. `totals` counts every thread
+// in the process, from the start.
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::Cell;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub allocations: usize,
+    pub deallocations: usize,
+    // A `realloc` moves or resizes a block, it's neither of the above
+    pub reallocations: usize,
+    pub bytes_allocated: usize,
+    pub bytes_freed: usize,
+    // The most bytes live at once, counting from the start of the
+    // measurement
+    pub peak_bytes: usize,
+}
+
+impl Stats {
+    // Bytes allocated and not freed again. Negative if more was freed than
+    // allocated, e.g. by dropping a value that was made before.
+    pub fn live_bytes(&self) -> isize {
+        self.bytes_allocated as isize - self.bytes_freed as isize
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} allocs, {} frees, {} reallocs, {} bytes live, {} peak",
+            self.allocations,
+            self.deallocations,
+            self.reallocations,
+            self.live_bytes(),
+            self.peak_bytes
+        )
+    }
+}
+
+thread_local! {
+    // `None` outside of `measure`. Const-initialized and without a
+    // destructor, so using it never allocates, which would recurse.
+    static CURRENT: Cell<Option<Stats>> = const { Cell::new(None) };
+}
+
+// The process-wide counters behind `totals`
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static REALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static BYTES_FREED: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+// Every thread's allocations since the program started. Zero unless
+// `Counting` is the global allocator. The counters are read one at a
+// time, so while other threads allocate they needn't match each other
+// exactly.
+pub fn totals() -> Stats {
+    Stats {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+        reallocations: REALLOCATIONS.load(Ordering::Relaxed),
+        bytes_allocated: BYTES_ALLOCATED.load(Ordering::Relaxed),
+        bytes_freed: BYTES_FREED.load(Ordering::Relaxed),
+        peak_bytes: PEAK_BYTES.load(Ordering::Relaxed),
+    }
+}
+
+fn record_alloc(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    BYTES_ALLOCATED.fetch_add(size, Ordering::Relaxed);
+    let live = LIVE_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+    record(|s| {
+        s.allocations += 1;
+        s.bytes_allocated += size;
+    });
+}
+
+fn record_dealloc(size: usize) {
+    DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    BYTES_FREED.fetch_add(size, Ordering::Relaxed);
+    LIVE_BYTES.fetch_sub(size, Ordering::Relaxed);
+    record(|s| {
+        s.deallocations += 1;
+        s.bytes_freed += size;
+    });
+}
+
+fn record_realloc(old_size: usize, new_size: usize) {
+    REALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    BYTES_FREED.fetch_add(old_size, Ordering::Relaxed);
+    BYTES_ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+    let live = if new_size >= old_size {
+        let grown = new_size - old_size;
+        LIVE_BYTES.fetch_add(grown, Ordering::Relaxed) + grown
+    } else {
+        LIVE_BYTES.fetch_sub(old_size - new_size, Ordering::Relaxed) - (old_size - new_size)
+    };
+    PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+    record(|s| {
+        s.reallocations += 1;
+        s.bytes_freed += old_size;
+        s.bytes_allocated += new_size;
+    });
+}
+
+// Updates the current thread's `measure`, if there is one
+fn record(update: impl FnOnce(&mut Stats)) {
+    // `try_with` fails while the thread is being torn down; nothing is
+    // being measured then anyway
+    let _ = CURRENT.try_with(|current| {
+        if let Some(mut stats) = current.get() {
+            update(&mut stats);
+            stats.peak_bytes = stats.peak_bytes.max(stats.live_bytes().max(0) as usize);
+            current.set(Some(stats));
+        }
+    });
+}
+
+pub struct Counting<A> {
+    inner: A,
+}
+
+impl<A> Counting<A> {
+    pub const fn new(inner: A) -> Counting<A> {
+        Counting { inner }
+    }
+}
+
+// Safety: every call is forwarded to `inner` unchanged; the counting
+// neither allocates nor touches the memory
+unsafe impl<A: GlobalAlloc> GlobalAlloc for Counting<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        record_dealloc(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = self.inner.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            record_realloc(layout.size(), new_size);
+        }
+        new
+    }
+}
+
+// Runs `f` and counts the allocations it makes on this thread. Everything
+// is zero unless `Counting` is the global allocator.
+//
+// Only the current thread is counted: allocations made by threads that `f`
+// starts or talks to are missed, see `totals` for those.
+//
+// Measurements can nest; the outer one includes the inner one, also when
+// `f` panics.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Stats) {
+    let mut scope = Scope {
+        outer: Some(CURRENT.with(|current| current.replace(Some(Stats::default())))),
+    };
+    let result = f();
+    (result, scope.end())
+}
+
+// Restores the enclosing measurement, if any, when `measure` returns or
+// `f` unwinds
+struct Scope {
+    // `None` once ended
+    outer: Option<Option<Stats>>,
+}
+
+impl Scope {
+    // Returns this measurement's counts and adds them to the outer one
+    fn end(&mut self) -> Stats {
+        let outer = match self.outer.take() {
+            Some(outer) => outer,
+            None => return Stats::default(),
+        };
+        let inner = CURRENT.with(|current| current.get()).unwrap_or_default();
+
+        let merged = outer.map(|mut stats| {
+            // The inner peak sits on top of whatever the outer one had live
+            let base = stats.live_bytes().max(0) as usize;
+            stats.peak_bytes = stats.peak_bytes.max(base + inner.peak_bytes);
+            stats.allocations += inner.allocations;
+            stats.deallocations += inner.deallocations;
+            stats.reallocations += inner.reallocations;
+            stats.bytes_allocated += inner.bytes_allocated;
+            stats.bytes_freed += inner.bytes_freed;
+            stats
+        });
+        CURRENT.with(|current| current.set(merged));
+        inner
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        self.end();
+    }
+}
+
+// The binary installs `Counting` as the global allocator, and so does the
+// test harness built from it
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{boxed_origin, origin, Point, Rectangle};
+    use std::mem;
+    use std::panic;
+    use std::thread;
+
+    #[test]
+    fn box_costs_its_contents() {
+        let (boxed, stats) = measure(|| {
+            Box::new(Rectangle {
+                top_left: origin(),
+                bottom_right: origin(),
+            })
+        });
+        assert_eq!(stats.allocations, 1);
+        assert_eq!(stats.live_bytes(), mem::size_of::<Rectangle>() as isize);
+
+        let (_, freed) = measure(move || drop(boxed));
+        assert_eq!(freed.deallocations, 1);
+        assert_eq!(freed.live_bytes(), -(mem::size_of::<Rectangle>() as isize));
+    }
+
+    #[test]
+    fn box_in_a_box_costs_two_allocations() {
+        let (_boxed, stats) = measure(|| Box::new(boxed_origin()));
+        assert_eq!(stats.allocations, 2);
+        assert_eq!(
+            stats.live_bytes(),
+            (mem::size_of::<Point>() + mem::size_of::<Box<Point>>()) as isize
+        );
+    }
+
+    #[test]
+    fn collections() {
+        let (_, stats) = measure(|| {
+            let mut points = Vec::with_capacity(10);
+            points.extend((0..10).map(|_| origin()));
+        });
+        assert_eq!(stats.allocations, 1);
+        assert_eq!(stats.deallocations, 1);
+        assert_eq!(stats.live_bytes(), 0);
+        assert_eq!(stats.peak_bytes, 10 * mem::size_of::<Point>());
+
+        let (points, stats) = measure(|| (0..100).map(|_| origin()).collect::<Vec<_>>());
+        assert_eq!(
+            stats.live_bytes(),
+            (points.capacity() * mem::size_of::<Point>()) as isize
+        );
+    }
+
+    #[test]
+    fn nothing_counted_outside_measure() {
+        let _boxed = Box::new(origin());
+        assert_eq!(CURRENT.with(|current| current.get()), None);
+    }
+
+    #[test]
+    fn nested_measurements_add_up() {
+        let (inner, outer) = measure(|| {
+            let _a = Box::new(1u64);
+            let (_, inner) = measure(|| Box::new([0u8; 64]));
+            inner
+        });
+        assert_eq!(inner.allocations, 1);
+        assert_eq!(inner.peak_bytes, 64);
+        assert_eq!(outer.allocations, 2);
+        assert_eq!(outer.live_bytes(), 0);
+        assert_eq!(outer.peak_bytes, 8 + 64);
+    }
+
+    #[test]
+    fn panic_restores_the_outer_measurement() {
+        let (_, outer) = measure(|| {
+            let _before = Box::new(1u64);
+            let caught = panic::catch_unwind(|| measure(|| panic!("inside measure")));
+            assert!(caught.is_err());
+            let _after = Box::new(2u64);
+        });
+        // Both boxes, and whatever the panic allocated in between
+        assert!(outer.allocations >= 2);
+        assert!(outer.bytes_allocated >= 16);
+        assert_eq!(CURRENT.with(|current| current.get()), None);
+    }
+
+    #[test]
+    fn other_threads_only_show_in_totals() {
+        const SIZE: usize = 1 << 20;
+        let before = totals();
+        let (_, stats) = measure(|| thread::spawn(|| vec![0u8; SIZE]).join().unwrap());
+        let after = totals();
+
+        // The vector was allocated on the spawned thread
+        assert!(stats.bytes_allocated < SIZE);
+        assert!(after.bytes_allocated - before.bytes_allocated >= SIZE);
+        assert!(after.peak_bytes >= SIZE);
+    }
+}
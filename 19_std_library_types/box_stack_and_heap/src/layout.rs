@@ -0,0 +1,216 @@
+// Where each field of a struct lives in memory, and how much of the struct
+// is padding.
+//
+// `inspect!(Point { x, y })` implements `Inspect` for `Point`; the field
+// types, offsets, sizes and alignments are all taken from the compiler, so
+// the list of names is all it needs. Leaving a field out is a compile error.
+// Any type with visible fields works, generic or behind a path, and tuple
+// fields go by their index: `inspect!(std::num::Wrapping<u64> { 0 })`.
+//
+// With the default representation rustc is free to reorder fields, and
+// already does so to cut padding. A `#[repr(C)]` struct keeps the
+// declaration order, and that's where `Layout::suggestion` finds savings.
+
+use std::fmt;
+use std::mem;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    pub type_name: &'static str,
+    pub offset: usize,
+    pub size: usize,
+    pub align: usize,
+}
+
+pub trait Inspect: Sized {
+    // In declaration order
+    fn fields() -> Vec<Field>;
+
+    fn layout() -> Layout {
+        Layout {
+            name: std::any::type_name::<Self>(),
+            size: mem::size_of::<Self>(),
+            align: mem::align_of::<Self>(),
+            fields: Self::fields(),
+        }
+    }
+}
+
+// Used by `inspect!`. `_get` is never called, it's only there for its
+// type, which is the type of the field.
+pub fn field<T, F>(name: &'static str, offset: usize, _get: fn(&T) -> &F) -> Field {
+    Field {
+        name,
+        type_name: std::any::type_name::<F>(),
+        offset,
+        size: mem::size_of::<F>(),
+        align: mem::align_of::<F>(),
+    }
+}
+
+macro_rules! inspect {
+    ($ty:ty { $($field:tt),+ $(,)? }) => {
+        impl $crate::layout::Inspect for $ty {
+            fn fields() -> Vec<$crate::layout::Field> {
+                // Doesn't compile unless every field is listed. A pattern
+                // needs a path, not a type, which the alias provides.
+                #[allow(dead_code)]
+                fn all_fields_listed(value: &$ty) {
+                    type Inspected = $ty;
+                    let Inspected { $($field: _),+ } = value;
+                }
+
+                vec![$(
+                    $crate::layout::field(
+                        stringify!($field),
+                        std::mem::offset_of!($ty, $field),
+                        |value: &$ty| &value.$field,
+                    )
+                ),+]
+            }
+        }
+    };
+}
+
+pub(crate) use inspect;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub name: &'static str,
+    pub size: usize,
+    pub align: usize,
+    pub fields: Vec<Field>,
+}
+
+// A field order that makes the type smaller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub order: Vec<&'static str>,
+    pub size: usize,
+}
+
+impl Layout {
+    // The fields as they are laid out in memory
+    pub fn in_memory_order(&self) -> Vec<Field> {
+        let mut fields = self.fields.clone();
+        // Zero-sized fields can share an offset with the next one
+        fields.sort_by_key(|f| (f.offset, f.size));
+        fields
+    }
+
+    // Unused bytes between the end of each field and whatever comes next,
+    // in memory order
+    pub fn padding_after(&self) -> Vec<(Field, usize)> {
+        let fields = self.in_memory_order();
+        let ends = fields.iter().skip(1).map(|f| f.offset).chain([self.size]);
+        fields
+            .iter()
+            .zip(ends)
+            .map(|(f, next)| (*f, next - (f.offset + f.size)))
+            .collect()
+    }
+
+    // Every byte that isn't part of a field
+    pub fn padding(&self) -> usize {
+        self.size - self.fields.iter().map(|f| f.size).sum::<usize>()
+    }
+
+    // Whether the compiler moved fields away from the declaration order
+    pub fn is_reordered(&self) -> bool {
+        self.in_memory_order()
+            .iter()
+            .map(|f| f.name)
+            .ne(self.fields.iter().map(|f| f.name))
+    }
+
+    // Laying out the fields from the most to the least strictly aligned
+    // leaves padding only at the end. That's as small as the type can get
+    // without changing any field types, so if it's smaller than the type is
+    // now, that order is the suggestion.
+    pub fn suggestion(&self) -> Option<Suggestion> {
+        let mut fields = self.fields.clone();
+        // Stable, so fields with the same alignment keep their order
+        fields.sort_by_key(|f| std::cmp::Reverse(f.align));
+        let size = sequential_size(&fields, self.align);
+        if size < self.size {
+            Some(Suggestion {
+                order: fields.iter().map(|f| f.name).collect(),
+                size,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+// The size of a struct with these fields in this order, as `#[repr(C)]`
+// would lay it out
+fn sequential_size(fields: &[Field], align: usize) -> usize {
+    let end = fields.iter().fold(0usize, |offset, f| {
+        offset.next_multiple_of(f.align) + f.size
+    });
+    end.next_multiple_of(align)
+}
+
+// `alloc::vec::Vec<my_crate::Point>` -> `Vec<Point>`
+fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut path = String::new();
+    for c in name.chars().chain(['\0']) {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+        } else {
+            short.push_str(path.rsplit("::").next().unwrap_or_default());
+            path.clear();
+            if c != '\0' {
+                short.push(c);
+            }
+        }
+    }
+    short
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} bytes, align {}, {} bytes of padding",
+            short_type_name(self.name),
+            self.size,
+            self.align,
+            self.padding()
+        )?;
+        writeln!(
+            f,
+            "{:>6} {:>5} {:>5} {:>5}  field",
+            "offset", "size", "align", "pad"
+        )?;
+        for (field, padding) in self.padding_after() {
+            writeln!(
+                f,
+                "{:>6} {:>5} {:>5} {:>5}  {}: {}",
+                field.offset,
+                field.size,
+                field.align,
+                padding,
+                field.name,
+                short_type_name(field.type_name)
+            )?;
+        }
+
+        if self.is_reordered() {
+            writeln!(f, "(fields reordered by the compiler)")?;
+        }
+        match self.suggestion() {
+            Some(s) => write!(
+                f,
+                "reordering as {} would shrink it from {} to {} bytes",
+                s.order.join(", "),
+                self.size,
+                s.size
+            ),
+            None => write!(f, "no reordering makes it smaller"),
+        }
+    }
+}
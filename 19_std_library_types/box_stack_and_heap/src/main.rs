@@ -5,8 +5,20 @@
 //
 // Boxed values can be dereferenced using the `*` operator; this removes one layer of indirection.
 
+mod counting;
+mod layout;
+
+use counting::{measure, totals, Counting};
+use layout::{inspect, Inspect};
+use std::alloc::System;
+use std::collections::HashMap;
 use std::mem;
 
+// Counts allocations made inside `measure`, and otherwise just passes them
+// on to the system allocator
+#[global_allocator]
+static ALLOCATOR: Counting<System> = Counting::new(System);
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 struct Point {
@@ -22,6 +34,57 @@ struct Rectangle {
     bottom_right: Point,
 }
 
+inspect!(Point { x, y });
+inspect!(Rectangle {
+    top_left,
+    bottom_right
+});
+
+// `#[repr(C)]` keeps the fields in this order, with padding after `flag`
+// and `kind` to align the fields that follow them
+#[allow(dead_code)]
+#[repr(C)]
+struct Packet {
+    flag: bool,
+    id: u64,
+    kind: u8,
+    len: u32,
+}
+
+inspect!(Packet {
+    flag,
+    id,
+    kind,
+    len
+});
+
+// The same fields, left for the compiler to arrange
+#[allow(dead_code)]
+struct RustPacket {
+    flag: bool,
+    id: u64,
+    kind: u8,
+    len: u32,
+}
+
+inspect!(RustPacket {
+    flag,
+    id,
+    kind,
+    len
+});
+
+// Generic types are inspected one instantiation at a time
+#[allow(dead_code)]
+struct Tagged<T> {
+    tag: u8,
+    value: T,
+}
+
+inspect!(Tagged<u32> { tag, value });
+inspect!(Tagged<Point> { tag, value });
+inspect!(std::num::Wrapping<u64> { 0 });
+
 fn origin() -> Point {
     Point { x: 0.0, y: 0.0 }
 }
@@ -41,16 +104,19 @@ fn main() {
     };
 
     // Heap allocated rectangle
-    let boxed_retangle: Box<Rectangle> = Box::new(Rectangle {
-        top_left: origin(),
-        bottom_right: Point { x: 3.0, y: -4.0 },
+    let (boxed_retangle, rectangle_heap) = measure(|| -> Box<Rectangle> {
+        Box::new(Rectangle {
+            top_left: origin(),
+            bottom_right: Point { x: 3.0, y: -4.0 },
+        })
     });
 
     // The output of the function can be boxed
-    let boxed_point: Box<Point> = Box::new(origin());
+    let (boxed_point, point_heap) = measure(|| -> Box<Point> { Box::new(origin()) });
 
     // Double indirection
-    let box_in_a_box: Box<Box<Point>> = Box::new(boxed_origin());
+    let (box_in_a_box, box_in_a_box_heap) =
+        measure(|| -> Box<Box<Point>> { Box::new(boxed_origin()) });
 
     println!(
         "Point occupies {} bytes on the stack",
@@ -62,21 +128,84 @@ fn main() {
         mem::size_of_val(&rectangle)
     );
 
-    // box size == pointer size
+    // box size == pointer size, the value itself is on the heap
+    println!(
+        "Boxed point occupies {} bytes on the stack and {} on the heap",
+        mem::size_of_val(&boxed_point),
+        point_heap.live_bytes()
+    );
+
     println!(
-        "Boxed point occupies {} bytes on the heap",
-        mem::size_of_val(&boxed_point)
+        "Boxed rectangle occupies {} bytes on the stack and {} on the heap",
+        mem::size_of_val(&boxed_retangle),
+        rectangle_heap.live_bytes()
     );
 
+    // Two allocations: the point, and the inner box that points to it
     println!(
-        "Boxed rectangle occupies {} bytes on the heap",
-        mem::size_of_val(&boxed_retangle)
+        "Box in a box occupies {} bytes on the stack and {} on the heap, in {} allocations",
+        mem::size_of_val(&box_in_a_box),
+        box_in_a_box_heap.live_bytes(),
+        box_in_a_box_heap.allocations
+    );
+    assert_eq!(
+        rectangle_heap.live_bytes(),
+        mem::size_of::<Rectangle>() as isize
+    );
+    assert_eq!(
+        box_in_a_box_heap.live_bytes(),
+        (mem::size_of::<Point>() + mem::size_of::<Box<Point>>()) as isize
     );
 
-    // Copy the data contained in `boxed_point` into `unboxed_point`
-    let unboxed_point: Point = *boxed_point;
+    // Copy the data contained in `boxed_point` into `unboxed_point`, and
+    // free the box
+    let (unboxed_point, unboxing) = measure(move || -> Point { *boxed_point });
     println!(
-        "Unboxed point occupies {} bytes on the stack",
-        mem::size_of_val(&unboxed_point)
+        "Unboxed point occupies {} bytes on the stack, and unboxing freed {} bytes",
+        mem::size_of_val(&unboxed_point),
+        -unboxing.live_bytes()
     );
+
+    // A growing `Vec` reallocates, and its peak can be well above what it
+    // ends up holding
+    let (points, vec_heap) = measure(|| {
+        let mut points = Vec::new();
+        for _ in 0..100 {
+            points.push(origin());
+        }
+        points
+    });
+    println!(
+        "{} points in a Vec with capacity {}: {}",
+        points.len(),
+        points.capacity(),
+        vec_heap
+    );
+
+    let (_, presized_heap) = measure(|| {
+        let mut points = Vec::with_capacity(100);
+        points.extend((0..100).map(|_| origin()));
+        points.len()
+    });
+    println!("The same, presized and then dropped: {}", presized_heap);
+    assert_eq!(presized_heap.allocations, 1);
+    assert_eq!(presized_heap.live_bytes(), 0);
+    assert_eq!(presized_heap.peak_bytes, 100 * mem::size_of::<Point>());
+
+    let (names, map_heap) = measure(|| {
+        (0..100)
+            .map(|i| (i, format!("point {}", i)))
+            .collect::<HashMap<_, _>>()
+    });
+    println!("A HashMap of {} strings: {}", names.len(), map_heap);
+    println!("The whole program so far: {}", totals());
+
+    // Where those bytes go, field by field
+    println!("\n{}", Point::layout());
+    println!("\n{}", Rectangle::layout());
+    println!("\n{}", Packet::layout());
+    println!("\n{}", RustPacket::layout());
+    println!("\n{}", Tagged::<u32>::layout());
+    println!("\n{}", Tagged::<Point>::layout());
+    println!("\n{}", std::num::Wrapping::<u64>::layout());
 }
@@ -0,0 +1,386 @@
+// An account store that never keeps passwords around.
+//
+// Accounts are keyed by username alone. Each one stores a random salt and
+// the PBKDF2-HMAC-SHA256 hash of its password, and a login hashes the
+// attempt the same way and compares the two in constant time. After
+// `Policy::max_failures` wrong passwords in a row the account is locked
+// until `unlock` is called.
+
+use pbkdf2::pbkdf2_hmac;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use subtle::ConstantTimeEq;
+
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 32;
+const FILE_HEADER: &str = "accounts v1";
+
+#[derive(Debug, Clone, Copy)]
+pub struct Policy {
+    // PBKDF2 rounds for new hashes. Accounts hashed with fewer rounds are
+    // rehashed on their next successful login.
+    pub iterations: u32,
+    pub max_failures: u32,
+    pub min_password_len: usize,
+}
+
+impl Default for Policy {
+    // The rounds OWASP recommends for PBKDF2-HMAC-SHA256 as of 2023
+    fn default() -> Policy {
+        Policy {
+            iterations: 600_000,
+            max_failures: 5,
+            min_password_len: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountInfo {
+    pub name: String,
+    pub email: String,
+}
+
+struct PasswordHash {
+    iterations: u32,
+    salt: [u8; SALT_LEN],
+    hash: [u8; HASH_LEN],
+}
+
+impl PasswordHash {
+    fn new(password: &str, iterations: u32) -> PasswordHash {
+        let mut salt = [0; SALT_LEN];
+        getrandom::getrandom(&mut salt).expect("no randomness available for the salt");
+        PasswordHash::with_salt(password, salt, iterations)
+    }
+
+    fn with_salt(password: &str, salt: [u8; SALT_LEN], iterations: u32) -> PasswordHash {
+        let mut hash = [0; HASH_LEN];
+        pbkdf2_hmac::<Sha256>(password.as_bytes(), &salt, iterations, &mut hash);
+        PasswordHash {
+            iterations,
+            salt,
+            hash,
+        }
+    }
+
+    fn verify(&self, password: &str) -> bool {
+        let attempt = PasswordHash::with_salt(password, self.salt, self.iterations);
+        // `==` on slices returns at the first differing byte, which tells
+        // an attacker timing the login how much of the hash they got right
+        attempt.hash.ct_eq(&self.hash).into()
+    }
+}
+
+struct Account {
+    info: AccountInfo,
+    password: PasswordHash,
+    failures: u32,
+}
+
+// What a caller learns about a failed login. Whether to tell the user
+// more than "login failed" is up to the caller: distinguishing unknown
+// users from wrong passwords lets anyone probe for valid usernames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginError {
+    UnknownUser,
+    WrongPassword { attempts_left: u32 },
+    // Too many failed attempts. Even the right password is refused.
+    Locked,
+}
+
+impl fmt::Display for LoginError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoginError::UnknownUser => write!(f, "unknown user"),
+            LoginError::WrongPassword { attempts_left } => {
+                write!(f, "wrong password, {} attempt(s) left", attempts_left)
+            }
+            LoginError::Locked => write!(f, "account locked after too many failed logins"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Login(LoginError),
+    UsernameTaken,
+    // Usernames must be non-empty and free of whitespace and control
+    // characters, which also keeps the file format simple
+    InvalidUsername,
+    PasswordTooShort { min: usize },
+    Io(io::Error),
+    Corrupt { line: usize, message: &'static str },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Login(e) => write!(f, "{}", e),
+            Error::UsernameTaken => write!(f, "username is already taken"),
+            Error::InvalidUsername => write!(f, "invalid username"),
+            Error::PasswordTooShort { min } => {
+                write!(f, "password must be at least {} characters", min)
+            }
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Corrupt { line, message } => {
+                write!(f, "corrupt account file, line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl From<LoginError> for Error {
+    fn from(e: LoginError) -> Error {
+        Error::Login(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+pub struct AccountStore {
+    policy: Policy,
+    accounts: HashMap<String, Account>,
+    // Hashed against on logins for unknown users, so that they take as
+    // long as logins for known ones
+    dummy: PasswordHash,
+}
+
+impl AccountStore {
+    pub fn new(policy: Policy) -> AccountStore {
+        AccountStore {
+            policy,
+            accounts: HashMap::new(),
+            dummy: PasswordHash::new("", policy.iterations),
+        }
+    }
+
+    pub fn register(
+        &mut self,
+        username: &str,
+        password: &str,
+        info: AccountInfo,
+    ) -> Result<(), Error> {
+        if !valid_username(username) {
+            return Err(Error::InvalidUsername);
+        }
+        if self.accounts.contains_key(username) {
+            return Err(Error::UsernameTaken);
+        }
+        self.check_password(password)?;
+
+        let account = Account {
+            info,
+            password: PasswordHash::new(password, self.policy.iterations),
+            failures: 0,
+        };
+        self.accounts.insert(username.to_string(), account);
+        Ok(())
+    }
+
+    pub fn login(&mut self, username: &str, password: &str) -> Result<&AccountInfo, LoginError> {
+        let policy = self.policy;
+        let account = match self.accounts.get_mut(username) {
+            Some(account) => account,
+            None => {
+                self.dummy.verify(password);
+                return Err(LoginError::UnknownUser);
+            }
+        };
+
+        if account.failures >= policy.max_failures {
+            return Err(LoginError::Locked);
+        }
+        if !account.password.verify(password) {
+            account.failures += 1;
+            return Err(match policy.max_failures - account.failures {
+                0 => LoginError::Locked,
+                attempts_left => LoginError::WrongPassword { attempts_left },
+            });
+        }
+
+        account.failures = 0;
+        if account.password.iterations < policy.iterations {
+            account.password = PasswordHash::new(password, policy.iterations);
+        }
+        Ok(&account.info)
+    }
+
+    // Needs the current password, and counts a wrong one as a failed login
+    pub fn change_password(&mut self, username: &str, old: &str, new: &str) -> Result<(), Error> {
+        self.check_password(new)?;
+        self.login(username, old)?;
+
+        let iterations = self.policy.iterations;
+        let account = self.accounts.get_mut(username).unwrap();
+        account.password = PasswordHash::new(new, iterations);
+        Ok(())
+    }
+
+    pub fn is_locked(&self, username: &str) -> bool {
+        self.accounts
+            .get(username)
+            .is_some_and(|account| account.failures >= self.policy.max_failures)
+    }
+
+    pub fn unlock(&mut self, username: &str) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get_mut(username)
+            .ok_or(Error::Login(LoginError::UnknownUser))?;
+        account.failures = 0;
+        Ok(())
+    }
+
+    // One account per line, fields separated by tabs:
+    // username, name, email, iterations, salt, hash, failed attempts
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+
+        let mut file = BufWriter::new(create_private(&tmp)?);
+        writeln!(file, "{}", FILE_HEADER)?;
+
+        // Sorted, so that saving the same store twice gives the same file
+        let mut usernames: Vec<&String> = self.accounts.keys().collect();
+        usernames.sort();
+        for username in usernames {
+            let account = &self.accounts[username];
+            writeln!(
+                file,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                username,
+                clean(&account.info.name),
+                clean(&account.info.email),
+                account.password.iterations,
+                to_hex(&account.password.salt),
+                to_hex(&account.password.hash),
+                account.failures
+            )?;
+        }
+
+        file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        // Readers see either the old file or the new one, never half of it
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P, policy: Policy) -> Result<AccountStore, Error> {
+        let mut store = AccountStore::new(policy);
+        let mut lines = BufReader::new(File::open(path)?).lines();
+
+        if lines.next().transpose()?.as_deref() != Some(FILE_HEADER) {
+            return Err(Error::Corrupt {
+                line: 1,
+                message: "not an account file",
+            });
+        }
+
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            let corrupt = |message| Error::Corrupt {
+                line: i + 2,
+                message,
+            };
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [username, name, email, iterations, salt, hash, failures] = fields[..] else {
+                return Err(corrupt("expected 7 fields"));
+            };
+            if !valid_username(username) {
+                return Err(corrupt("invalid username"));
+            }
+
+            let password = PasswordHash {
+                iterations: iterations
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| corrupt("invalid iteration count"))?,
+                salt: from_hex(salt).ok_or_else(|| corrupt("invalid salt"))?,
+                hash: from_hex(hash).ok_or_else(|| corrupt("invalid hash"))?,
+            };
+            let account = Account {
+                info: AccountInfo {
+                    name: name.to_string(),
+                    email: email.to_string(),
+                },
+                password,
+                failures: failures
+                    .parse()
+                    .map_err(|_| corrupt("invalid failure count"))?,
+            };
+
+            if store
+                .accounts
+                .insert(username.to_string(), account)
+                .is_some()
+            {
+                return Err(corrupt("duplicate username"));
+            }
+        }
+
+        Ok(store)
+    }
+
+    fn check_password(&self, password: &str) -> Result<(), Error> {
+        let min = self.policy.min_password_len;
+        if password.chars().count() < min {
+            return Err(Error::PasswordTooShort { min });
+        }
+        Ok(())
+    }
+}
+
+fn valid_username(username: &str) -> bool {
+    !username.is_empty()
+        && !username
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control())
+}
+
+// Tabs and newlines would break the line format
+fn clean(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+// The file holds password hashes, so only its owner may read it
+#[cfg(unix)]
+fn create_private(path: &Path) -> io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private(path: &Path) -> io::Result<File> {
+    File::create(path)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    // Checked by hand, `from_str_radix` would accept a `+` sign
+    if hex.len() != N * 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
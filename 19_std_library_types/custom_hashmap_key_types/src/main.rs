@@ -1,53 +1,80 @@
-use std::collections::HashMap;
+mod auth;
 
-// Eq requires that you derive PartialEq on the type.
+use auth::{AccountInfo, AccountStore, Error, LoginError, Policy};
+use std::env;
+use std::fs;
 
-#[derive(PartialEq, Eq, Hash)]
-struct Account<'a> {
-    username: &'a str,
-    password: &'a str,
-}
-
-struct AccountInfo<'a> {
-    name: &'a str,
-    email: &'a str,
-}
-
-type Accounts<'a> = HashMap<Account<'a>, AccountInfo<'a>>;
-
-fn try_logon<'a>(accounts: &Accounts<'a>, username: &'a str, password: &'a str) {
+fn try_logon(accounts: &mut AccountStore, username: &str, password: &str) {
     println!("Username: {}", username);
-    println!("Password: {}", password);
     println!("Attempting to logon...");
 
-    let logon = Account { username, password };
-
-    match accounts.get(&logon) {
-        Some(account_info) => {
+    match accounts.login(username, password) {
+        Ok(account_info) => {
             println!("Successful login!");
             println!("Name: {}", account_info.name);
             println!("Email: {}", account_info.email);
         }
-        _ => println!("Login failed!"),
+        Err(e) => println!("Login failed: {}", e),
     }
 }
 
-fn main() {
-    let mut accounts: Accounts = HashMap::new();
-
-    let account = Account {
-        username: "j.everyman",
-        password: "password123",
+fn main() -> Result<(), Error> {
+    // Far fewer rounds than the default, so that the example runs quickly
+    // in a debug build. Don't do this for real accounts.
+    let policy = Policy {
+        iterations: 10_000,
+        max_failures: 3,
+        ..Policy::default()
     };
+    let mut accounts = AccountStore::new(policy);
 
     let account_info = AccountInfo {
-        name: "John Everyman",
-        email: "j.everyman@email.com",
+        name: "John Everyman".to_string(),
+        email: "j.everyman@email.com".to_string(),
     };
+    accounts.register("j.everyman", "password123", account_info)?;
+
+    if let Err(e) = accounts.register(
+        "j.everyman",
+        "something else",
+        AccountInfo {
+            name: "Impostor".to_string(),
+            email: "impostor@email.com".to_string(),
+        },
+    ) {
+        println!("Can't register j.everyman twice: {}", e);
+    }
+
+    try_logon(&mut accounts, "j.everyman", "psasword123");
+    try_logon(&mut accounts, "j.everyman", "password123");
+    try_logon(&mut accounts, "j.noone", "password123");
+
+    if let Err(e) = accounts.change_password("j.everyman", "password123", "short") {
+        println!("Password change refused: {}", e);
+    }
+    accounts.change_password("j.everyman", "password123", "correct horse battery staple")?;
+    try_logon(&mut accounts, "j.everyman", "password123");
+
+    // The store is saved without a single password in it
+    let path = env::temp_dir().join(format!("accounts-{}", std::process::id()));
+    accounts.save(&path)?;
+    println!("{}", fs::read_to_string(&path)?);
+
+    // Failed attempts survive a restart, so a lockout can't be dodged by
+    // waiting for one
+    let mut accounts = AccountStore::load(&path, policy)?;
+    for _ in 0..2 {
+        try_logon(&mut accounts, "j.everyman", "letmein!");
+    }
+    assert!(accounts.is_locked("j.everyman"));
 
-    accounts.insert(account, account_info);
+    // Even the right password is refused now
+    let locked = accounts.login("j.everyman", "correct horse battery staple");
+    assert_eq!(locked.err(), Some(LoginError::Locked));
 
-    try_logon(&accounts, "j.everyman", "psasword123");
+    accounts.unlock("j.everyman")?;
+    try_logon(&mut accounts, "j.everyman", "correct horse battery staple");
 
-    try_logon(&accounts, "j.everyman", "password123");
+    fs::remove_file(&path)?;
+    Ok(())
 }
@@ -0,0 +1,540 @@
+// A contact book keyed by name, with typed and validated phone numbers.
+//
+// Contacts live in a `HashMap` keyed by their lowercased name, so lookups
+// ignore case and adding "daniel" replaces "Daniel". The book is saved as
+// JSON and can be exchanged with other programs as vCard 3.0.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PhoneKind {
+    Mobile,
+    Home,
+    Work,
+    Other,
+}
+
+impl PhoneKind {
+    fn vcard_type(self) -> &'static str {
+        match self {
+            PhoneKind::Mobile => "CELL",
+            PhoneKind::Home => "HOME",
+            PhoneKind::Work => "WORK",
+            PhoneKind::Other => "VOICE",
+        }
+    }
+
+    // The most specific kind among a `TEL`'s types, `Other` if none is known
+    fn from_vcard_types<'a>(types: impl Iterator<Item = &'a str>) -> PhoneKind {
+        let mut kind = PhoneKind::Other;
+        for ty in types {
+            match ty.to_ascii_uppercase().as_str() {
+                "CELL" => return PhoneKind::Mobile,
+                "HOME" => kind = PhoneKind::Home,
+                "WORK" if kind == PhoneKind::Other => kind = PhoneKind::Work,
+                _ => {}
+            }
+        }
+        kind
+    }
+}
+
+impl fmt::Display for PhoneKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PhoneKind::Mobile => "mobile",
+            PhoneKind::Home => "home",
+            PhoneKind::Work => "work",
+            PhoneKind::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhoneError {
+    pub input: String,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for PhoneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid phone number `{}`: {}", self.input, self.reason)
+    }
+}
+
+// A phone number in canonical form: an optional leading `+` followed by
+// 3 to 15 digits, the most E.164 allows
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PhoneNumber(String);
+
+impl PhoneNumber {
+    // Accepts the usual separators, "(555) 123-4567", "555.123.4567",
+    // "+30 210 1234567", and turns an international `00` prefix into `+`
+    pub fn parse(input: &str) -> Result<PhoneNumber, PhoneError> {
+        let err = |reason| PhoneError {
+            input: input.to_string(),
+            reason,
+        };
+
+        let trimmed = input.trim();
+        let (international, rest) = match trimmed.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => match trimmed.strip_prefix("00") {
+                Some(rest) => (true, rest),
+                None => (false, trimmed),
+            },
+        };
+
+        let mut digits = String::with_capacity(rest.len());
+        for c in rest.chars() {
+            match c {
+                '0'..='9' => digits.push(c),
+                ' ' | '-' | '.' | '(' | ')' => {}
+                _ => {
+                    return Err(err(
+                        "only digits, spaces, `-`, `.`, `(` and `)` are allowed",
+                    ))
+                }
+            }
+        }
+
+        if digits.len() < 3 {
+            return Err(err("too few digits"));
+        }
+        if digits.len() > 15 {
+            return Err(err("more than 15 digits"));
+        }
+
+        if international {
+            digits.insert(0, '+');
+        }
+        Ok(PhoneNumber(digits))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Numbers read from a file go through `parse` again, so a hand-edited file
+// can't sneak an invalid number in
+impl TryFrom<String> for PhoneNumber {
+    type Error = PhoneError;
+
+    fn try_from(s: String) -> Result<PhoneNumber, PhoneError> {
+        PhoneNumber::parse(&s)
+    }
+}
+
+impl From<PhoneNumber> for String {
+    fn from(number: PhoneNumber) -> String {
+        number.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Phone {
+    pub kind: PhoneKind,
+    pub number: PhoneNumber,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contact {
+    pub name: String,
+    pub phones: Vec<Phone>,
+}
+
+impl Contact {
+    pub fn new(name: &str) -> Contact {
+        Contact {
+            name: name.trim().to_string(),
+            phones: Vec::new(),
+        }
+    }
+
+    pub fn phone(mut self, kind: PhoneKind, number: &str) -> Result<Contact, PhoneError> {
+        self.add_phone(kind, number)?;
+        Ok(self)
+    }
+
+    // Adding a number the contact already has only updates its kind
+    pub fn add_phone(&mut self, kind: PhoneKind, number: &str) -> Result<(), PhoneError> {
+        let number = PhoneNumber::parse(number)?;
+        match self.phones.iter_mut().find(|phone| phone.number == number) {
+            Some(phone) => phone.kind = kind,
+            None => self.phones.push(Phone { kind, number }),
+        }
+        Ok(())
+    }
+
+    // The first number of the given kind
+    pub fn number(&self, kind: PhoneKind) -> Option<&PhoneNumber> {
+        self.phones
+            .iter()
+            .find(|phone| phone.kind == kind)
+            .map(|phone| &phone.number)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    VCard { line: usize, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Json(e) => write!(f, "invalid contact file: {}", e),
+            Error::VCard { line, message } => write!(f, "vCard line {}: {}", line, message),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::Json(e)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ContactBook {
+    contacts: HashMap<String, Contact>,
+}
+
+impl ContactBook {
+    pub fn new() -> ContactBook {
+        ContactBook::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.contacts.len()
+    }
+
+    // Returns the contact that had the same name, if any
+    pub fn insert(&mut self, contact: Contact) -> Option<Contact> {
+        self.contacts.insert(contact.name.to_lowercase(), contact)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Contact> {
+        self.contacts.get(&name.trim().to_lowercase())
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Contact> {
+        self.contacts.get_mut(&name.trim().to_lowercase())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Contact> {
+        self.contacts.remove(&name.trim().to_lowercase())
+    }
+
+    // All contacts, sorted by name
+    pub fn contacts(&self) -> Vec<&Contact> {
+        let mut contacts: Vec<&Contact> = self.contacts.values().collect();
+        contacts.sort_by_key(|contact| contact.name.to_lowercase());
+        contacts
+    }
+
+    // Contacts whose name, or any word of it, starts with `prefix`,
+    // ignoring case: "sm" finds "Ashley Smith"
+    pub fn search_prefix(&self, prefix: &str) -> Vec<&Contact> {
+        let prefix = prefix.trim().to_lowercase();
+        self.contacts()
+            .into_iter()
+            .filter(|contact| {
+                let name = contact.name.to_lowercase();
+                name.starts_with(&prefix) || name.split_whitespace().any(|w| w.starts_with(&prefix))
+            })
+            .collect()
+    }
+
+    // Contacts whose name, or any word of it, is at most `max_distance`
+    // edits away from `query`, closest first
+    pub fn search_fuzzy(&self, query: &str, max_distance: usize) -> Vec<(&Contact, usize)> {
+        let query = query.trim().to_lowercase();
+        let mut found: Vec<(&Contact, usize)> = self
+            .contacts()
+            .into_iter()
+            .filter_map(|contact| {
+                let name = contact.name.to_lowercase();
+                let distance = name
+                    .split_whitespace()
+                    .map(|word| levenshtein(&query, word))
+                    .chain(Some(levenshtein(&query, &name)))
+                    .min()?;
+                (distance <= max_distance).then_some((contact, distance))
+            })
+            .collect();
+        // Stable, so equally close contacts stay sorted by name
+        found.sort_by_key(|&(_, distance)| distance);
+        found
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        // Sorted, so that saving the same book twice gives the same file
+        serde_json::to_writer_pretty(&mut writer, &self.contacts())?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ContactBook, Error> {
+        let reader = BufReader::new(File::open(path)?);
+        let contacts: Vec<Contact> = serde_json::from_reader(reader)?;
+
+        let mut book = ContactBook::new();
+        for contact in contacts {
+            book.insert(contact);
+        }
+        Ok(book)
+    }
+
+    pub fn to_vcard(&self) -> String {
+        let mut out = String::new();
+        for contact in self.contacts() {
+            write_vcard(&mut out, contact);
+        }
+        out
+    }
+
+    // Adds every card in `text` to the book and returns how many there
+    // were. Properties other than `FN`, `N` and `TEL` are ignored.
+    pub fn import_vcard(&mut self, text: &str) -> Result<usize, Error> {
+        let contacts = parse_vcards(text)?;
+        let count = contacts.len();
+        for contact in contacts {
+            self.insert(contact);
+        }
+        Ok(count)
+    }
+}
+
+// vCard 3.0, RFC 2426. Lines end in CRLF and are folded at 75 octets.
+fn write_vcard(out: &mut String, contact: &Contact) {
+    let escaped = escape(&contact.name);
+
+    // `N` is required: family name, then given names. We only have a full
+    // name, so the last word is taken to be the family name.
+    let n = match contact.name.rsplit_once(' ') {
+        Some((given, family)) => format!("{};{};;;", escape(family), escape(given.trim())),
+        None => format!(";{};;;", escaped),
+    };
+
+    let mut lines = vec![
+        "BEGIN:VCARD".to_string(),
+        "VERSION:3.0".to_string(),
+        format!("FN:{}", escaped),
+        format!("N:{}", n),
+    ];
+    for phone in &contact.phones {
+        lines.push(format!(
+            "TEL;TYPE={}:{}",
+            phone.kind.vcard_type(),
+            phone.number
+        ));
+    }
+    lines.push("END:VCARD".to_string());
+
+    for line in lines {
+        fold(out, &line);
+    }
+}
+
+fn fold(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        // Continuation lines start with a space, which counts towards the 75
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ',' | ';' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+// Splits `text` on `sep`, except where `sep` is escaped with a backslash
+fn split_unescaped(text: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            _ if c == sep => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+fn parse_vcards(text: &str) -> Result<Vec<Contact>, Error> {
+    let err = |line, message: &str| Error::VCard {
+        line,
+        message: message.to_string(),
+    };
+
+    // Unfold first: a line starting with a space or a tab continues the
+    // previous one. Each logical line keeps the number of its first line.
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match raw.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().1.push_str(rest),
+            _ if raw.trim().is_empty() => {}
+            _ => lines.push((i + 1, raw.to_string())),
+        }
+    }
+
+    let mut contacts = Vec::new();
+    // The name from `FN`, the one built from `N` and the phones of the card
+    // being read, `None` outside of a card
+    let mut card: Option<(Option<String>, Option<String>, Vec<Phone>)> = None;
+
+    for (number, line) in lines {
+        let (head, value) = line
+            .split_once(':')
+            .ok_or_else(|| err(number, "expected `NAME:value`"))?;
+        let mut params = head.split(';');
+        // Property names may carry a group prefix, `item1.TEL`
+        let name = params.next().unwrap_or_default();
+        let name = name.rsplit('.').next().unwrap_or(name).to_ascii_uppercase();
+
+        match (name.as_str(), card.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VCARD") => {
+                card = Some((None, None, Vec::new()))
+            }
+            ("BEGIN", Some(_)) => return Err(err(number, "nested `BEGIN:VCARD`")),
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VCARD") => {
+                let (full_name, n_name, phones) = card.take().unwrap();
+                let name = full_name
+                    .or(n_name)
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(|| err(number, "card without a name"))?;
+                let mut contact = Contact::new(&name);
+                contact.phones = phones;
+                contacts.push(contact);
+            }
+            (_, None) => return Err(err(number, "property outside of a card")),
+            ("FN", Some((full_name, _, _))) => {
+                *full_name = Some(unescape(value).trim().to_string())
+            }
+            ("N", Some((_, n_name, _))) => {
+                // family;given;additional;prefixes;suffixes
+                let parts: Vec<String> = split_unescaped(value, ';')
+                    .into_iter()
+                    .map(unescape)
+                    .collect();
+                let order = [3, 1, 2, 0, 4];
+                let words: Vec<&str> = order
+                    .iter()
+                    .filter_map(|&i| parts.get(i))
+                    .map(|part| part.trim())
+                    .filter(|part| !part.is_empty())
+                    .collect();
+                *n_name = Some(words.join(" "));
+            }
+            ("TEL", Some((_, _, phones))) => {
+                // Both `TYPE=WORK,VOICE` and the older bare `WORK;VOICE`
+                let types = params.flat_map(|param| {
+                    let param = match param.split_once('=') {
+                        Some((key, value)) if key.eq_ignore_ascii_case("TYPE") => value,
+                        Some(_) => "",
+                        None => param,
+                    };
+                    param.split(',')
+                });
+                let kind = PhoneKind::from_vcard_types(types);
+                let number = PhoneNumber::parse(value).map_err(|e| err(number, &e.to_string()))?;
+                if !phones.iter().any(|phone| phone.number == number) {
+                    phones.push(Phone { kind, number });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if card.is_some() {
+        return Err(err(text.lines().count(), "missing `END:VCARD`"));
+    }
+    Ok(contacts)
+}
+
+// Number of single-character insertions, deletions and substitutions
+// turning `a` into `b`
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        cur[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
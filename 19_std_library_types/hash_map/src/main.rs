@@ -1,12 +1,17 @@
-use std::collections::HashMap;
+mod contacts;
 
-fn call(number: &str) -> &str {
-    match number {
-        "798-1364" => {
-            "We're sorry, the call cannot be completed as dialed. 
+use contacts::{Contact, ContactBook, PhoneKind, PhoneNumber};
+use std::env;
+use std::fs;
+
+fn call(number: &PhoneNumber) -> &str {
+    // Numbers are stored in canonical form, whatever way they were typed in
+    match number.as_str() {
+        "7981364" => {
+            "We're sorry, the call cannot be completed as dialed.
             Please hang up and try again."
         }
-        "645-7689" => {
+        "6457689" => {
             "Hello, this is Mr. Awesome's Pizza. My name is Fred.
             What can I get for you today?"
         }
@@ -14,34 +19,94 @@ fn call(number: &str) -> &str {
     }
 }
 
-fn main() {
-    let mut contacts: HashMap<&str, &str> = HashMap::new();
+fn main() -> Result<(), contacts::Error> {
+    let mut book = ContactBook::new();
 
-    contacts.insert("Daniel", "798-1364");
-    contacts.insert("Ashley", "645-7689");
-    contacts.insert("Katie", "435-8291");
-    contacts.insert("Robert", "956-1745");
+    let people = [
+        ("Daniel", PhoneKind::Mobile, "798-1364"),
+        ("Ashley Smith", PhoneKind::Work, "(645) 7689"),
+        ("Katie", PhoneKind::Home, "435.8291"),
+        ("Robert Smithers", PhoneKind::Mobile, "0030 210 956 1745"),
+    ];
+    for (name, kind, number) in people {
+        book.insert(Contact::new(name).phone(kind, number).unwrap());
+    }
+    book.get_mut("katie")
+        .unwrap()
+        .add_phone(PhoneKind::Mobile, "+1 555 010 0199")
+        .unwrap();
 
-    // Takes a reference and returns Option<&V>
-    match contacts.get(&"Daniel") {
-        Some(&number) => println!("Calling Daniel: {}", call(number)),
+    // Lookups ignore case
+    match book.get("DANIEL").and_then(|c| c.number(PhoneKind::Mobile)) {
+        Some(number) => println!("Calling Daniel: {}", call(number)),
         _ => println!("Don't have Daniel's number"),
     }
 
-    // `HashMap::insert()` returns `None` if the inserted value is new,
-    // and `Some(value)` otherwise.
-    contacts.insert("Daniel", "164-6743");
+    // Invalid numbers are rejected instead of stored
+    if let Err(e) = Contact::new("Nobody").phone(PhoneKind::Home, "555-CALL-NOW") {
+        println!("{}", e);
+    }
+
+    // Inserting a contact with the same name returns the old one
+    let old = book.insert(
+        Contact::new("Daniel")
+            .phone(PhoneKind::Mobile, "164-6743")
+            .unwrap(),
+    );
+    println!("Replaced {:?}", old.map(|c| c.name));
 
-    match contacts.get(&"Ashley") {
-        Some(&number) => println!("Calling Ashley: {}", call(number)),
-        _ => println!("Don't have Ashley's number"),
+    println!("Prefix `sm`:");
+    for contact in book.search_prefix("sm") {
+        println!("  {}", contact.name);
+    }
+    println!("Fuzzy `smyth`:");
+    for (contact, distance) in book.search_fuzzy("smyth", 2) {
+        println!("  {} ({} edit(s))", contact.name, distance);
     }
 
-    contacts.remove(&"Ashley");
+    for contact in book.contacts() {
+        for phone in &contact.phones {
+            println!(
+                "Calling {} ({}, {}): {}",
+                contact.name,
+                phone.kind,
+                phone.number,
+                call(&phone.number)
+            );
+        }
+    }
 
-    // `HashMap::iter()` returns an iterator that yields (&'a key, &'a value)
-    // pairs in arbitrary order.
-    for (contact, &number) in contacts.iter() {
-        println!("Calling {}: {}", contact, call(number));
+    if let Some(katie) = book.remove("KATIE") {
+        println!(
+            "Removed {} with {} number(s)",
+            katie.name,
+            katie.phones.len()
+        );
     }
+
+    let dir = env::temp_dir().join(format!("contacts-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+
+    let json = dir.join("contacts.json");
+    book.save(&json)?;
+    let loaded = ContactBook::load(&json)?;
+    assert_eq!(loaded, book);
+    println!("Saved and loaded {} contacts", loaded.len());
+
+    let vcard = book.to_vcard();
+    println!("{}", vcard.lines().take(7).collect::<Vec<_>>().join("\n"));
+    let mut imported = ContactBook::new();
+    imported.import_vcard(&vcard)?;
+    assert_eq!(imported, book);
+
+    // Cards from other programs: folded lines, groups, old-style types
+    let foreign = "BEGIN:VCARD\r\nVERSION:3.0\r\nN:O'Brien;Mary;Ann;;\r\n\
+                   item1.TEL;TYPE=work,voice:+44 20 7946 \r\n 0958\r\nTEL;CELL:07700 900123\r\n\
+                   EMAIL:mary@example.com\r\nEND:VCARD\r\n";
+    let count = imported.import_vcard(foreign)?;
+    let mary = imported.get("mary ann o'brien").unwrap();
+    println!("Imported {} card: {:?}", count, mary);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
 }
@@ -0,0 +1,90 @@
+// Compares `HashSet<usize>` with `BitSet` on two sets of `n` elements:
+// the multiples of 2 and the multiples of 3 below `3 * n`.
+//
+// Run it with `cargo run --release -- bench`, a debug build says more
+// about the optimizer than about the data structures.
+
+use crate::bitset::BitSet;
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+fn time<R>(f: impl FnOnce() -> R) -> (R, Duration) {
+    let start = Instant::now();
+    let result = black_box(f());
+    (result, start.elapsed())
+}
+
+fn report(name: &str, hash: Duration, bits: Duration) {
+    println!(
+        "{:<22} {:>12.2?} {:>12.2?} {:>9.1}x",
+        name,
+        hash,
+        bits,
+        hash.as_secs_f64() / bits.as_secs_f64().max(1e-9)
+    );
+}
+
+pub fn run(n: usize) {
+    let evens = || (0..n).map(|i| i * 2);
+    let threes = || (0..n).map(|i| i * 3);
+
+    println!("{} elements per set", n);
+    println!(
+        "{:<22} {:>12} {:>12} {:>10}",
+        "operation", "HashSet", "BitSet", "speedup"
+    );
+
+    let ((ha, hb), hash) = time(|| {
+        (
+            evens().collect::<HashSet<usize>>(),
+            threes().collect::<HashSet<usize>>(),
+        )
+    });
+    let ((ba, bb), bits) = time(|| (evens().collect::<BitSet>(), threes().collect::<BitSet>()));
+    report("build", hash, bits);
+
+    let (hu, hash) = time(|| ha.union(&hb).copied().collect::<HashSet<usize>>());
+    let (bu, bits) = time(|| ba.union(&bb));
+    report("union", hash, bits);
+    assert_eq!(hu.len(), bu.len());
+
+    let (hi, hash) = time(|| ha.intersection(&hb).copied().collect::<HashSet<usize>>());
+    let (bi, bits) = time(|| ba.intersection(&bb));
+    report("intersection", hash, bits);
+    assert_eq!(hi.len(), bi.len());
+
+    let (hd, hash) = time(|| ha.difference(&hb).copied().collect::<HashSet<usize>>());
+    let (bd, bits) = time(|| ba.difference(&bb));
+    report("difference", hash, bits);
+    assert_eq!(hd.len(), bd.len());
+
+    let (hs, hash) = time(|| {
+        ha.symmetric_difference(&hb)
+            .copied()
+            .collect::<HashSet<usize>>()
+    });
+    let (bs, bits) = time(|| ba.symmetric_difference(&bb));
+    report("symmetric difference", hash, bits);
+    assert_eq!(hs.len(), bs.len());
+
+    let (hc, hash) = time(|| (0..n).filter(|&i| ha.contains(&i)).count());
+    let (bc, bits) = time(|| (0..n).filter(|&i| ba.contains(i)).count());
+    report("contains", hash, bits);
+    assert_eq!(hc, bc);
+
+    // Summed rather than collected, so only the walk is timed. The
+    // `BitSet` yields its elements in ascending order on top of that.
+    let (hsum, hash) = time(|| ha.iter().sum::<usize>());
+    let (bsum, bits) = time(|| ba.iter().sum::<usize>());
+    report("iterate", hash, bits);
+    assert_eq!(hsum, bsum);
+
+    // A `HashSet` stores each element plus a control byte per bucket
+    let hash_bytes = ha.capacity() * (std::mem::size_of::<usize>() + 1);
+    println!(
+        "memory for one set: HashSet ~{} MiB, BitSet {} MiB",
+        hash_bytes >> 20,
+        ba.memory() >> 20
+    );
+}
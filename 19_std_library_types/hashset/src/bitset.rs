@@ -0,0 +1,213 @@
+// A set of small non-negative integers, one bit per possible element.
+//
+// Element `n` is bit `n % 64` of word `n / 64`. Set operations work on
+// whole words, 64 elements per instruction, and iteration walks the set
+// bits of each word, which yields the elements in ascending order. The
+// price is memory proportional to the largest element rather than to the
+// number of elements, so it suits dense sets.
+
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+
+const BITS: usize = u64::BITS as usize;
+
+#[derive(Clone, Default)]
+pub struct BitSet {
+    // Trailing zero words are allowed, so equality is implemented by hand
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new() -> BitSet {
+        BitSet::default()
+    }
+
+    // Room for the elements `0..n` without reallocating
+    pub fn with_capacity(n: usize) -> BitSet {
+        BitSet {
+            words: Vec::with_capacity(n.div_ceil(BITS)),
+        }
+    }
+
+    // Returns false if `n` was already present, like `HashSet::insert`
+    pub fn insert(&mut self, n: usize) -> bool {
+        let (word, mask) = (n / BITS, 1 << (n % BITS));
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let absent = self.words[word] & mask == 0;
+        self.words[word] |= mask;
+        absent
+    }
+
+    // Returns true if `n` was present
+    pub fn remove(&mut self, n: usize) -> bool {
+        let (word, mask) = (n / BITS, 1 << (n % BITS));
+        match self.words.get_mut(word) {
+            Some(w) if *w & mask != 0 => {
+                *w &= !mask;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn contains(&self, n: usize) -> bool {
+        self.words
+            .get(n / BITS)
+            .is_some_and(|w| w & (1 << (n % BITS)) != 0)
+    }
+
+    // Counts the bits of every word, so it's linear in the largest element
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    // Bytes used by the bits themselves
+    pub fn memory(&self) -> usize {
+        self.words.capacity() * std::mem::size_of::<u64>()
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            words: &self.words,
+            index: 0,
+            current: self.words.first().copied().unwrap_or(0),
+        }
+    }
+
+    pub fn union(&self, other: &BitSet) -> BitSet {
+        self.combine(other, |a, b| a | b)
+    }
+
+    pub fn intersection(&self, other: &BitSet) -> BitSet {
+        self.combine(other, |a, b| a & b)
+    }
+
+    pub fn difference(&self, other: &BitSet) -> BitSet {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    pub fn symmetric_difference(&self, other: &BitSet) -> BitSet {
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    pub fn is_subset(&self, other: &BitSet) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    // Applies `op` word by word. Missing words count as zero, so the
+    // result is as long as the longer of the two.
+    fn combine(&self, other: &BitSet, op: impl Fn(u64, u64) -> u64) -> BitSet {
+        let len = self.words.len().max(other.words.len());
+        let word = |words: &[u64], i| words.get(i).copied().unwrap_or(0);
+        let mut words: Vec<u64> = (0..len)
+            .map(|i| op(word(&self.words, i), word(&other.words, i)))
+            .collect();
+
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+        BitSet { words }
+    }
+}
+
+pub struct Iter<'a> {
+    words: &'a [u64],
+    index: usize,
+    // The bits of `words[index]` not yielded yet
+    current: u64,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            self.index += 1;
+            self.current = *self.words.get(self.index)?;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        // Clears the lowest set bit
+        self.current &= self.current - 1;
+        Some(self.index * BITS + bit)
+    }
+}
+
+impl<'a> IntoIterator for &'a BitSet {
+    type Item = usize;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> BitSet {
+        let mut set = BitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for n in iter {
+            self.insert(n);
+        }
+    }
+}
+
+impl PartialEq for BitSet {
+    fn eq(&self, other: &BitSet) -> bool {
+        self.symmetric_difference(other).is_empty()
+    }
+}
+
+impl Eq for BitSet {}
+
+impl fmt::Debug for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+// `&a | &b` and friends, as for `HashSet`
+
+impl BitOr<&BitSet> for &BitSet {
+    type Output = BitSet;
+
+    fn bitor(self, other: &BitSet) -> BitSet {
+        self.union(other)
+    }
+}
+
+impl BitAnd<&BitSet> for &BitSet {
+    type Output = BitSet;
+
+    fn bitand(self, other: &BitSet) -> BitSet {
+        self.intersection(other)
+    }
+}
+
+impl Sub<&BitSet> for &BitSet {
+    type Output = BitSet;
+
+    fn sub(self, other: &BitSet) -> BitSet {
+        self.difference(other)
+    }
+}
+
+impl BitXor<&BitSet> for &BitSet {
+    type Output = BitSet;
+
+    fn bitxor(self, other: &BitSet) -> BitSet {
+        self.symmetric_difference(other)
+    }
+}
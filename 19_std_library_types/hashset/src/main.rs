@@ -1,6 +1,50 @@
+mod bench;
+mod bitset;
+mod setops;
+
+use bitset::BitSet;
 use std::collections::HashSet;
+use std::env;
+use std::process;
+
+const USAGE: &str =
+    "usage: hashset <union|intersection|difference|symmetric-difference|comm> FILE FILE...
+       hashset bench [N]";
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => examples(),
+        Some("bench") => {
+            let n = match args.get(1).map(|n| n.parse()) {
+                None => 10_000_000,
+                Some(Ok(n)) => n,
+                Some(Err(e)) => {
+                    eprintln!("invalid N: {}\n{}", e, USAGE);
+                    process::exit(2);
+                }
+            };
+            bench::run(n);
+        }
+        Some(_) => match setops::run(&args) {
+            Ok(lines) => {
+                for line in lines {
+                    println!("{}", line);
+                }
+            }
+            Err(e @ setops::Error::Usage(_)) => {
+                eprintln!("{}\n{}", e, USAGE);
+                process::exit(2);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        },
+    }
+}
+
+fn examples() {
     let mut a: HashSet<i32> = vec![1i32, 2, 3].into_iter().collect();
     let mut b: HashSet<i32> = vec![2i32, 3, 4].into_iter().collect();
 
@@ -36,4 +80,50 @@ fn main() {
         "Symmetric Difference: {:?}",
         a.symmetric_difference(&b).collect::<Vec<&i32>>()
     );
+
+    // The same operations on a `BitSet`, which always iterates in order
+    let a: BitSet = [1, 2, 3, 4].into_iter().collect();
+    let b: BitSet = [2, 3, 4, 5].into_iter().collect();
+    println!("BitSet A: {:?}", a);
+    println!("BitSet B: {:?}", b);
+    println!("Union: {:?}", &a | &b);
+    println!("Difference: {:?}", &a - &b);
+    println!("Intersection: {:?}", &a & &b);
+    println!("Symmetric Difference: {:?}", &a ^ &b);
+
+    // Memory grows with the largest element, not the number of elements
+    let mut sparse = BitSet::new();
+    sparse.insert(1_000_000);
+    println!(
+        "{} element(s), {} bytes, contains 1000000: {}",
+        sparse.len(),
+        sparse.memory(),
+        sparse.contains(1_000_000)
+    );
+    assert!(sparse.remove(1_000_000) && sparse.is_empty());
+    assert!((&a & &b).is_subset(&a));
+
+    // Set operations on files: `hashset intersection FILE FILE...`
+    let dir = env::temp_dir().join(format!("hashset-{}", process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (monday, tuesday) = (dir.join("monday"), dir.join("tuesday"));
+    std::fs::write(&monday, "alice\nbob\ncarol\n").unwrap();
+    std::fs::write(&tuesday, "carol\ndave\nalice\n").unwrap();
+
+    let args = |op: &str| -> Vec<String> {
+        vec![
+            op.to_string(),
+            monday.display().to_string(),
+            tuesday.display().to_string(),
+        ]
+    };
+    println!(
+        "Came both days: {:?}",
+        setops::run(&args("intersection")).unwrap()
+    );
+    println!("comm:");
+    for line in setops::run(&args("comm")).unwrap() {
+        println!("{}", line);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
 }
@@ -0,0 +1,150 @@
+// Set operations on the lines of files, a little like `comm`.
+//
+// Every file is read into a `HashSet` of its lines, so duplicates within
+// a file count once and the files don't need to be sorted. Results are
+// printed sorted, which keeps the output stable across runs.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    // Lines in any file
+    Union,
+    // Lines in every file
+    Intersection,
+    // Lines of the first file that are in none of the others
+    Difference,
+    // Lines in exactly one of the files
+    SymmetricDifference,
+    // Two files only: three columns, lines only in the first, lines only
+    // in the second and lines in both
+    Comm,
+}
+
+impl FromStr for Op {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Op, Error> {
+        match s {
+            "union" => Ok(Op::Union),
+            "intersection" => Ok(Op::Intersection),
+            "difference" => Ok(Op::Difference),
+            "symmetric-difference" => Ok(Op::SymmetricDifference),
+            "comm" => Ok(Op::Comm),
+            _ => Err(Error::Usage(
+                "unknown operation, expected union, intersection, difference, \
+                 symmetric-difference or comm",
+            )),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Usage(&'static str),
+    Io { path: PathBuf, source: io::Error },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Usage(message) => write!(f, "{}", message),
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+        }
+    }
+}
+
+pub fn read_lines(path: &Path) -> Result<HashSet<String>, Error> {
+    let text = fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(text.lines().map(str::to_string).collect())
+}
+
+// The lines `op` selects from `sets`, sorted. `Op::Comm` needs exactly
+// two sets, any other number is a usage error.
+pub fn apply(op: Op, sets: &[HashSet<String>]) -> Result<Vec<String>, Error> {
+    let (first, rest) = match sets.split_first() {
+        Some(split) => split,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut lines: Vec<&str> = match op {
+        // Duplicates across files are removed after sorting
+        Op::Union => sets.iter().flatten().map(String::as_str).collect(),
+        Op::Intersection => first
+            .iter()
+            .filter(|line| rest.iter().all(|set| set.contains(*line)))
+            .map(String::as_str)
+            .collect(),
+        Op::Difference => first
+            .iter()
+            .filter(|line| !rest.iter().any(|set| set.contains(*line)))
+            .map(String::as_str)
+            .collect(),
+        // With two sets this is `a.symmetric_difference(&b)`. Chaining
+        // that over more sets would keep lines found in an odd number of
+        // files, which is rarely what anyone wants.
+        Op::SymmetricDifference => sets
+            .iter()
+            .flatten()
+            .filter(|line| sets.iter().filter(|set| set.contains(*line)).count() == 1)
+            .map(String::as_str)
+            .collect(),
+        Op::Comm => {
+            return match rest {
+                [second] => Ok(comm(first, second)),
+                _ => Err(Error::Usage("`comm` compares exactly two sets")),
+            };
+        }
+    };
+
+    lines.sort_unstable();
+    lines.dedup();
+    Ok(lines.into_iter().map(str::to_string).collect())
+}
+
+// Like `comm a b`: lines only in `a` start in column one, lines only in
+// `b` are indented by one tab and lines in both by two. Sorted as a whole.
+pub fn comm(a: &HashSet<String>, b: &HashSet<String>) -> Vec<String> {
+    let mut columns: Vec<(&str, usize)> = a
+        .difference(b)
+        .map(|line| (line.as_str(), 0))
+        .chain(b.difference(a).map(|line| (line.as_str(), 1)))
+        .chain(a.intersection(b).map(|line| (line.as_str(), 2)))
+        .collect();
+    columns.sort_unstable();
+
+    columns
+        .into_iter()
+        .map(|(line, column)| format!("{}{}", "\t".repeat(column), line))
+        .collect()
+}
+
+// Runs `<operation> FILE FILE...` and returns the lines to print
+pub fn run(args: &[String]) -> Result<Vec<String>, Error> {
+    let (op, paths) = args
+        .split_first()
+        .ok_or(Error::Usage("missing operation"))?;
+    let op: Op = op.parse()?;
+
+    if paths.len() < 2 {
+        return Err(Error::Usage("need at least two files"));
+    }
+    if op == Op::Comm && paths.len() != 2 {
+        return Err(Error::Usage("`comm` compares exactly two files"));
+    }
+
+    let sets = paths
+        .iter()
+        .map(|path| read_lines(Path::new(path)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    apply(op, &sets)
+}
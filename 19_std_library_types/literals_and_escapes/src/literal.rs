@@ -0,0 +1,358 @@
+// Rust string literals at runtime: `parse` decodes the source text of a
+// literal the way the compiler would, and `escape_str` and `escape_bytes`
+// go the other way.
+//
+// Supported are `"..."`, `r"..."`, `r#"..."#` with any number of `#`s,
+// and the byte string forms `b"..."`, `br"..."` and `br#"..."#`.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Literal {
+    Str(String),
+    Bytes(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    // Doesn't start with `"`, `r`, `b` or `br`
+    NotALiteral,
+    Unterminated,
+    // Anything after the closing quote
+    TrailingCharacters,
+    UnknownEscape(char),
+    // `\x` needs exactly two hex digits
+    InvalidHexEscape,
+    // `\x80` and above are only allowed in byte strings
+    NonAsciiHexEscape,
+    // `\u{...}`: 1 to 6 hex digits, naming a `char`
+    InvalidUnicodeEscape(&'static str),
+    UnicodeEscapeInByteString,
+    NonAsciiInByteString(char),
+    // A carriage return must be followed by a line feed
+    BareCarriageReturn,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    // Both 1-based, columns counted in `char`s
+    pub line: usize,
+    pub column: usize,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match &self.kind {
+            ErrorKind::NotALiteral => write!(f, "expected a string literal"),
+            ErrorKind::Unterminated => write!(f, "unterminated string literal"),
+            ErrorKind::TrailingCharacters => write!(f, "unexpected characters after the literal"),
+            ErrorKind::UnknownEscape(c) => write!(f, "unknown character escape `\\{}`", c),
+            ErrorKind::InvalidHexEscape => write!(f, "`\\x` must be followed by two hex digits"),
+            ErrorKind::NonAsciiHexEscape => {
+                write!(
+                    f,
+                    "`\\x` escapes above `\\x7f` are only allowed in byte strings"
+                )
+            }
+            ErrorKind::InvalidUnicodeEscape(reason) => {
+                write!(f, "invalid unicode escape: {}", reason)
+            }
+            ErrorKind::UnicodeEscapeInByteString => {
+                write!(f, "unicode escapes are not allowed in byte strings")
+            }
+            ErrorKind::NonAsciiInByteString(c) => {
+                write!(f, "non-ASCII character `{}` in a byte string", c)
+            }
+            ErrorKind::BareCarriageReturn => write!(f, "bare carriage return"),
+        }
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    bytes: bool,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error_at(&self, pos: usize, kind: ErrorKind) -> ParseError {
+        let before = &self.src[..pos];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            kind,
+        }
+    }
+
+    // Appends `c` to the output, as a `char` or, in a byte string, as one
+    // ASCII byte. `at` is where `c` came from, for the error.
+    fn push(&self, out: &mut Vec<u8>, c: char, at: usize) -> Result<(), ParseError> {
+        if self.bytes && !c.is_ascii() {
+            return Err(self.error_at(at, ErrorKind::NonAsciiInByteString(c)));
+        }
+        let mut buf = [0; 4];
+        out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        Ok(())
+    }
+
+    // The next source character, with CRLF read as a single `\n`
+    fn next_char(&mut self) -> Result<Option<char>, ParseError> {
+        let start = self.pos;
+        match self.bump() {
+            Some('\r') if self.eat('\n') => Ok(Some('\n')),
+            Some('\r') => Err(self.error_at(start, ErrorKind::BareCarriageReturn)),
+            c => Ok(c),
+        }
+    }
+
+    fn raw(&mut self, out: &mut Vec<u8>) -> Result<(), ParseError> {
+        let mut hashes = 0;
+        while self.eat('#') {
+            hashes += 1;
+        }
+        if !self.eat('"') {
+            return Err(self.error_at(self.pos, ErrorKind::NotALiteral));
+        }
+
+        let closing = format!("\"{}", "#".repeat(hashes));
+        loop {
+            if self.src[self.pos..].starts_with(&closing) {
+                self.pos += closing.len();
+                return Ok(());
+            }
+            let at = self.pos;
+            match self.next_char()? {
+                Some(c) => self.push(out, c, at)?,
+                None => return Err(self.error_at(self.pos, ErrorKind::Unterminated)),
+            }
+        }
+    }
+
+    fn quoted(&mut self, out: &mut Vec<u8>) -> Result<(), ParseError> {
+        if !self.eat('"') {
+            return Err(self.error_at(self.pos, ErrorKind::NotALiteral));
+        }
+        loop {
+            let at = self.pos;
+            match self.next_char()? {
+                None => return Err(self.error_at(self.pos, ErrorKind::Unterminated)),
+                Some('"') => return Ok(()),
+                Some('\\') => self.escape(out, at)?,
+                Some(c) => self.push(out, c, at)?,
+            }
+        }
+    }
+
+    // After a backslash at `start`
+    fn escape(&mut self, out: &mut Vec<u8>, start: usize) -> Result<(), ParseError> {
+        let c = match self.bump() {
+            Some(c) => c,
+            None => return Err(self.error_at(self.pos, ErrorKind::Unterminated)),
+        };
+        let simple = match c {
+            'n' => Some(b'\n'),
+            'r' => Some(b'\r'),
+            't' => Some(b'\t'),
+            '0' => Some(b'\0'),
+            '\\' | '\'' | '"' => Some(c as u8),
+            _ => None,
+        };
+        if let Some(byte) = simple {
+            out.push(byte);
+            return Ok(());
+        }
+
+        match c {
+            'x' => {
+                // Checked by hand, `from_str_radix` would accept a `+` sign
+                let digits = self.src[self.pos..]
+                    .get(..2)
+                    .filter(|d| d.bytes().all(|b| b.is_ascii_hexdigit()));
+                let byte = digits
+                    .and_then(|d| u8::from_str_radix(d, 16).ok())
+                    .ok_or_else(|| self.error_at(start, ErrorKind::InvalidHexEscape))?;
+                if byte > 0x7f && !self.bytes {
+                    return Err(self.error_at(start, ErrorKind::NonAsciiHexEscape));
+                }
+                self.pos += 2;
+                out.push(byte);
+            }
+            'u' if self.bytes => {
+                return Err(self.error_at(start, ErrorKind::UnicodeEscapeInByteString));
+            }
+            'u' => {
+                let c = self.unicode_escape(start)?;
+                self.push(out, c, start)?;
+            }
+            // A line continuation: the newline and the whitespace that
+            // follows it are skipped
+            '\n' | '\r' => {
+                if c == '\r' && !self.eat('\n') {
+                    return Err(self.error_at(start + 1, ErrorKind::BareCarriageReturn));
+                }
+                while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+                    self.bump();
+                }
+            }
+            c => return Err(self.error_at(start, ErrorKind::UnknownEscape(c))),
+        }
+        Ok(())
+    }
+
+    // After `\u` at `start`
+    fn unicode_escape(&mut self, start: usize) -> Result<char, ParseError> {
+        let invalid = |p: &Self, reason| p.error_at(start, ErrorKind::InvalidUnicodeEscape(reason));
+
+        if !self.eat('{') {
+            return Err(invalid(self, "expected `{`"));
+        }
+        let mut value: u32 = 0;
+        let mut digits = 0;
+        loop {
+            match self.bump() {
+                Some('}') if digits == 0 => return Err(invalid(self, "no hex digits")),
+                Some('}') => break,
+                // Underscores may separate digits, but not come first
+                Some('_') if digits > 0 => {}
+                Some(c) if c.is_ascii_hexdigit() => {
+                    digits += 1;
+                    if digits > 6 {
+                        return Err(invalid(self, "more than 6 hex digits"));
+                    }
+                    value = value * 16 + c.to_digit(16).unwrap();
+                }
+                _ => return Err(invalid(self, "expected hex digits and `}`")),
+            }
+        }
+        char::from_u32(value).ok_or_else(|| {
+            let reason = if value > 0x10ffff {
+                "above U+10FFFF"
+            } else {
+                "a surrogate code point"
+            };
+            invalid(self, reason)
+        })
+    }
+}
+
+pub fn parse(src: &str) -> Result<Literal, ParseError> {
+    let mut parser = Parser {
+        src,
+        pos: 0,
+        bytes: false,
+    };
+    parser.bytes = parser.eat('b');
+    let raw = parser.eat('r');
+
+    let mut out = Vec::new();
+    if raw {
+        parser.raw(&mut out)?;
+    } else {
+        parser.quoted(&mut out)?;
+    }
+    if parser.pos != src.len() {
+        return Err(parser.error_at(parser.pos, ErrorKind::TrailingCharacters));
+    }
+
+    Ok(if parser.bytes {
+        Literal::Bytes(out)
+    } else {
+        // Only whole `char`s and ASCII escapes were pushed
+        Literal::Str(String::from_utf8(out).expect("string literal decoded to invalid UTF-8"))
+    })
+}
+
+// The shortest literal for `s` that fits on one line: a raw string if
+// that's shorter, otherwise a quoted one with the fewest escapes
+pub fn escape_str(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\0' => quoted.push_str("\\0"),
+            c if c.is_ascii_control() => quoted.push_str(&format!("\\x{:02x}", c as u8)),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    // Raw strings can't escape anything, so control characters rule them out
+    if s.chars().any(char::is_control) {
+        return quoted;
+    }
+    shorter(quoted, raw("r", s))
+}
+
+// Like `escape_str`, for byte strings. Bytes outside of printable ASCII
+// become `\x` escapes.
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    let mut quoted = String::with_capacity(bytes.len() + 3);
+    quoted.push_str("b\"");
+    for &b in bytes {
+        match b {
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            b'\0' => quoted.push_str("\\0"),
+            b' '..=b'~' => quoted.push(b as char),
+            _ => quoted.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    quoted.push('"');
+
+    match std::str::from_utf8(bytes) {
+        Ok(s) if bytes.iter().all(|b| (b' '..=b'~').contains(b)) => shorter(quoted, raw("br", s)),
+        _ => quoted,
+    }
+}
+
+// `prefix"s"` with just enough `#`s that no `"` in `s` ends it early
+fn raw(prefix: &str, s: &str) -> String {
+    let mut hashes = 0;
+    // Each `"` followed by `n` hashes needs `n + 1` of them in the delimiter
+    for (i, _) in s.match_indices('"') {
+        let run = s[i + 1..].chars().take_while(|&c| c == '#').count();
+        hashes = hashes.max(run + 1);
+    }
+    let hashes = "#".repeat(hashes);
+    format!("{}{}\"{}\"{}", prefix, hashes, s, hashes)
+}
+
+// Ties go to the quoted form, which is the more familiar one
+fn shorter(quoted: String, raw: String) -> String {
+    if raw.chars().count() < quoted.chars().count() {
+        raw
+    } else {
+        quoted
+    }
+}
@@ -1,3 +1,6 @@
+mod literal;
+
+use literal::Literal;
 use std::str;
 
 fn main() {
@@ -61,9 +64,63 @@ fn main() {
     // Byte strings don't have to be UTF-8
     let shift_jis = b"\x82\xe6\x82\xa8\x82\xb1\x82\xbb"; // "ようこそ" in SHIFT-JIS
 
-    // But then they can't always be converted to `str`
+    // But then they can't always be converted to `str`. The compiler can
+    // tell that this one never will, and that's the point here.
+    #[allow(invalid_from_utf8)]
     match str::from_utf8(shift_jis) {
         Ok(my_str) => println!("Conversion successful: '{}'", my_str),
         Err(e) => println!("Conversion failed: {:?}", e),
     };
+
+    // The same syntax can be decoded at runtime, from text that isn't
+    // Rust source, like a config file
+    let sources = [
+        r#""I'm writing \x52\x75\x73\x74, \u{211D}""#,
+        "\"The linebreak and indentation here ->\\\n        <- can be escaped too!\"",
+        r####"r###"A string with "# in it. And even "##!"###"####,
+        r#"b"\x82\xe6\x82\xa8""#,
+        r#"br"\u{211D} is not escaped here""#,
+    ];
+    for source in sources {
+        match literal::parse(source) {
+            Ok(Literal::Str(s)) => println!("{} -> {:?}", source, s),
+            Ok(Literal::Bytes(b)) => println!("{} -> {:?}", source, b),
+            Err(e) => println!("{} -> {}", source, e),
+        }
+    }
+
+    // Errors point at the offending escape
+    let broken = [
+        r#""tab\qtab""#,
+        r#""\xFF is only for bytes""#,
+        r#""\u{D800}""#,
+        r#"b"caf\u{e9}""#,
+        "\"first line\n  second \\u{110000}\"",
+        r#""no end"#,
+    ];
+    for source in broken {
+        println!("{} -> {}", source, literal::parse(source).unwrap_err());
+    }
+
+    // And back: the shortest literal for any string or byte string
+    let values = [
+        "plain",
+        "C:\\Users\\ferris",
+        "say \"hi\"",
+        "a \"# b",
+        "tab\tand\u{85}next line",
+    ];
+    for value in values {
+        let escaped = literal::escape_str(value);
+        println!("{:?} -> {}", value, escaped);
+        assert_eq!(
+            literal::parse(&escaped),
+            Ok(Literal::Str(value.to_string()))
+        );
+    }
+    for bytes in [&b"\\d+\\.\\d+"[..], shift_jis] {
+        let escaped = literal::escape_bytes(bytes);
+        println!("{:?} -> {}", bytes, escaped);
+        assert_eq!(literal::parse(&escaped), Ok(Literal::Bytes(bytes.to_vec())));
+    }
 }
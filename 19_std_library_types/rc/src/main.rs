@@ -1,4 +1,7 @@
+mod tree;
+
 use std::rc::Rc;
+use tree::{MoveError, TreeNode};
 
 fn main() {
     let rc_example = "Rc examples".to_string();
@@ -32,4 +35,66 @@ fn main() {
     // Error! `rc_examples` already moved into `rc_a`
     // And when `rc_a` is dropped, `rc_examples` is dropped together
     // println!("rc_examples: {}", rc_example);
+
+    println!("--- a tree with weak parent links ---");
+    tree_example();
+}
+
+fn names(nodes: &[TreeNode<&'static str>]) -> Vec<&'static str> {
+    nodes.iter().map(|node| *node.value()).collect()
+}
+
+fn tree_example() {
+    let root = TreeNode::new("/");
+    let usr = root.insert("usr");
+    let bin = usr.insert("bin");
+    let lib = usr.insert("lib");
+    let home = root.insert("home");
+    let alice = home.insert("alice");
+    alice.insert("notes");
+
+    println!("Depth first:   {:?}", names(&root.depth_first()));
+    println!("Breadth first: {:?}", names(&root.breadth_first()));
+    println!(
+        "Path to root from notes: {:?}",
+        names(&alice.children()[0].path_to_root())
+    );
+
+    // The parent holds one strong reference, our handle the other. The
+    // children's links back to `usr` are weak and don't count.
+    println!("Strong count of usr: {}", usr.strong_count());
+
+    // Moving a directory below one of its own descendants is refused
+    assert_eq!(usr.move_to(&bin), Err(MoveError::WouldCreateCycle));
+    if let Err(e) = root.move_to(&root) {
+        println!("Moving / into itself: {}", e);
+    }
+
+    // `lib` and everything below it change parents
+    lib.move_to(&alice).unwrap();
+    println!("After moving lib: {:?}", names(&root.depth_first()));
+    println!("lib now lives at depth {}", lib.depth());
+
+    // A detached subtree stays alive as long as a handle to it does
+    let detached = home.clone();
+    home.detach();
+    println!("After detaching home: {:?}", names(&root.depth_first()));
+    println!("Detached subtree: {:?}", names(&detached.depth_first()));
+    usr.move_to(&detached).unwrap();
+    detached.set_value("/home");
+    println!("Reattached usr: {:?}", names(&detached.depth_first()));
+
+    // Keep only weak references to every node, then drop all the strong
+    // handles. Had the parent links been `Rc`s, the nodes would keep each
+    // other alive and every upgrade below would still succeed.
+    let nodes: Vec<_> = root
+        .depth_first()
+        .into_iter()
+        .chain(detached.depth_first())
+        .map(|node| node.downgrade())
+        .collect();
+    drop((root, usr, bin, lib, home, alice, detached));
+
+    assert!(nodes.iter().all(|node| node.upgrade().is_none()));
+    println!("All {} nodes were freed", nodes.len());
 }
@@ -0,0 +1,327 @@
+// A tree whose nodes own their children and point back at their parent.
+//
+// Children are held through `Rc`, parents through `Weak`. If parents were
+// `Rc` too, every parent and child would keep each other alive, the strong
+// counts would never reach zero and the whole tree would leak. With `Weak`
+// parent links, dropping the last handle to the root frees every node.
+
+use std::cell::{Ref, RefCell};
+use std::collections::VecDeque;
+use std::fmt;
+use std::mem;
+use std::rc::{Rc, Weak};
+
+struct Node<T> {
+    value: T,
+    parent: Weak<RefCell<Node<T>>>,
+    children: Vec<Rc<RefCell<Node<T>>>>,
+}
+
+impl<T> Drop for Node<T> {
+    // Left to itself, dropping a node would drop its children from inside
+    // its own drop, one stack frame per level, and a deep enough tree would
+    // overflow the stack. The descendants are freed from a stack of their
+    // own instead.
+    fn drop(&mut self) {
+        let mut stack = mem::take(&mut self.children);
+        while let Some(child) = stack.pop() {
+            // A node that is still held elsewhere keeps its children
+            if let Ok(node) = Rc::try_unwrap(child) {
+                let mut node = node.into_inner();
+                stack.append(&mut node.children);
+            }
+        }
+    }
+}
+
+// A handle to one node. Cloning it clones the `Rc`, not the node.
+pub struct TreeNode<T>(Rc<RefCell<Node<T>>>);
+
+// A handle that doesn't keep its node alive, see `TreeNode::downgrade`
+pub struct WeakNode<T>(Weak<RefCell<Node<T>>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    // A node can't become a child of itself or of its own descendant,
+    // that would turn the tree into a cycle
+    WouldCreateCycle,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::WouldCreateCycle => write!(f, "can't move a node below itself"),
+        }
+    }
+}
+
+impl<T> Clone for TreeNode<T> {
+    fn clone(&self) -> Self {
+        TreeNode(Rc::clone(&self.0))
+    }
+}
+
+impl<T> PartialEq for TreeNode<T> {
+    // Two handles are equal if they point at the same node
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TreeNode<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TreeNode({:?})", self.0.borrow().value)
+    }
+}
+
+impl<T> TreeNode<T> {
+    pub fn new(value: T) -> TreeNode<T> {
+        TreeNode(Rc::new(RefCell::new(Node {
+            value,
+            parent: Weak::new(),
+            children: Vec::new(),
+        })))
+    }
+
+    pub fn value(&self) -> Ref<'_, T> {
+        Ref::map(self.0.borrow(), |node| &node.value)
+    }
+
+    pub fn set_value(&self, value: T) {
+        self.0.borrow_mut().value = value;
+    }
+
+    // Creates a new node holding `value` as the last child of `self`
+    pub fn insert(&self, value: T) -> TreeNode<T> {
+        let child = TreeNode::new(value);
+        self.adopt(&child);
+        child
+    }
+
+    pub fn parent(&self) -> Option<TreeNode<T>> {
+        self.0.borrow().parent.upgrade().map(TreeNode)
+    }
+
+    pub fn children(&self) -> Vec<TreeNode<T>> {
+        self.0
+            .borrow()
+            .children
+            .iter()
+            .map(|child| TreeNode(Rc::clone(child)))
+            .collect()
+    }
+
+    // Removes `self` and its subtree from its parent. The subtree lives on
+    // for as long as there are handles to it.
+    pub fn detach(&self) {
+        let parent = self.0.borrow_mut().parent.upgrade();
+        if let Some(parent) = parent {
+            parent
+                .borrow_mut()
+                .children
+                .retain(|child| !Rc::ptr_eq(child, &self.0));
+            self.0.borrow_mut().parent = Weak::new();
+        }
+    }
+
+    // Moves `self`, with its subtree, to the end of `new_parent`'s children
+    pub fn move_to(&self, new_parent: &TreeNode<T>) -> Result<(), MoveError> {
+        if new_parent.path_to_root().contains(self) {
+            return Err(MoveError::WouldCreateCycle);
+        }
+        self.detach();
+        new_parent.adopt(self);
+        Ok(())
+    }
+
+    // `self`, its parent, its grandparent and so on up to the root
+    pub fn path_to_root(&self) -> Vec<TreeNode<T>> {
+        let mut path = vec![self.clone()];
+        while let Some(parent) = path[path.len() - 1].parent() {
+            path.push(parent);
+        }
+        path
+    }
+
+    pub fn depth(&self) -> usize {
+        self.path_to_root().len() - 1
+    }
+
+    // Pre-order: every node comes before its children
+    pub fn depth_first(&self) -> Vec<TreeNode<T>> {
+        let mut order = Vec::new();
+        let mut stack = vec![self.clone()];
+        while let Some(node) = stack.pop() {
+            // Reversed so that the first child is visited first
+            stack.extend(node.children().into_iter().rev());
+            order.push(node);
+        }
+        order
+    }
+
+    // Level by level, starting at `self`
+    pub fn breadth_first(&self) -> Vec<TreeNode<T>> {
+        let mut order = Vec::new();
+        let mut queue = VecDeque::from([self.clone()]);
+        while let Some(node) = queue.pop_front() {
+            queue.extend(node.children());
+            order.push(node);
+        }
+        order
+    }
+
+    pub fn downgrade(&self) -> WeakNode<T> {
+        WeakNode(Rc::downgrade(&self.0))
+    }
+
+    pub fn strong_count(&self) -> usize {
+        Rc::strong_count(&self.0)
+    }
+
+    fn adopt(&self, child: &TreeNode<T>) {
+        child.0.borrow_mut().parent = Rc::downgrade(&self.0);
+        self.0.borrow_mut().children.push(Rc::clone(&child.0));
+    }
+}
+
+impl<T> WeakNode<T> {
+    // `None` once the node has been freed
+    pub fn upgrade(&self) -> Option<TreeNode<T>> {
+        self.0.upgrade().map(TreeNode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // Counts how many values have been dropped
+    struct Counted<'a>(&'a Cell<usize>);
+
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    //       a
+    //     /   \
+    //    b     e
+    //   / \
+    //  c   d
+    fn sample() -> (TreeNode<char>, Vec<TreeNode<char>>) {
+        let a = TreeNode::new('a');
+        let b = a.insert('b');
+        let c = b.insert('c');
+        let d = b.insert('d');
+        let e = a.insert('e');
+        (a.clone(), vec![a, b, c, d, e])
+    }
+
+    fn values(nodes: &[TreeNode<char>]) -> String {
+        nodes.iter().map(|node| *node.value()).collect()
+    }
+
+    #[test]
+    fn dropping_the_root_frees_every_node() {
+        let dropped = Cell::new(0);
+        let root = TreeNode::new(Counted(&dropped));
+        let child = root.insert(Counted(&dropped));
+        child.insert(Counted(&dropped));
+        root.insert(Counted(&dropped));
+
+        let weak: Vec<_> = root.depth_first().iter().map(TreeNode::downgrade).collect();
+        drop(child);
+        assert_eq!(dropped.get(), 0);
+
+        drop(root);
+        assert_eq!(dropped.get(), 4);
+        assert!(weak.iter().all(|node| node.upgrade().is_none()));
+    }
+
+    #[test]
+    fn dropping_a_deep_chain_doesnt_overflow_the_stack() {
+        let dropped = Cell::new(0);
+        let root = TreeNode::new(Counted(&dropped));
+        let mut leaf = root.clone();
+        for _ in 0..200_000 {
+            leaf = leaf.insert(Counted(&dropped));
+        }
+        drop(leaf);
+
+        drop(root);
+        assert_eq!(dropped.get(), 200_001);
+    }
+
+    #[test]
+    fn parent_links_are_weak() {
+        let (root, nodes) = sample();
+        // The handle in `nodes` and the parent's `children`
+        assert_eq!(nodes[1].strong_count(), 2);
+        // `root`, and the handle in `nodes`
+        assert_eq!(root.strong_count(), 2);
+        assert_eq!(nodes[2].parent(), Some(nodes[1].clone()));
+    }
+
+    #[test]
+    fn detach_removes_the_subtree() {
+        let (root, nodes) = sample();
+        let b = &nodes[1];
+        b.detach();
+
+        assert_eq!(values(&root.depth_first()), "ae");
+        assert_eq!(b.parent(), None);
+        assert_eq!(values(&b.depth_first()), "bcd");
+        assert_eq!(b.strong_count(), 1);
+
+        // Detaching a root does nothing
+        root.detach();
+        assert_eq!(values(&root.depth_first()), "ae");
+    }
+
+    #[test]
+    fn detached_subtree_is_freed_with_its_last_handle() {
+        let (root, mut nodes) = sample();
+        let b = nodes.remove(1);
+        let weak: Vec<_> = b.depth_first().iter().map(TreeNode::downgrade).collect();
+        b.detach();
+        nodes.truncate(1);
+        drop(b);
+
+        assert!(weak.iter().all(|node| node.upgrade().is_none()));
+        assert_eq!(values(&root.depth_first()), "ae");
+    }
+
+    #[test]
+    fn move_to_refuses_cycles() {
+        let (root, nodes) = sample();
+        let (b, c) = (&nodes[1], &nodes[2]);
+
+        assert_eq!(b.move_to(b), Err(MoveError::WouldCreateCycle));
+        assert_eq!(b.move_to(c), Err(MoveError::WouldCreateCycle));
+        assert_eq!(root.move_to(c), Err(MoveError::WouldCreateCycle));
+        // Nothing changed
+        assert_eq!(values(&root.depth_first()), "abcde");
+    }
+
+    #[test]
+    fn move_to_changes_parents() {
+        let (root, nodes) = sample();
+        let (b, e) = (&nodes[1], &nodes[4]);
+
+        b.move_to(e).unwrap();
+        assert_eq!(values(&root.depth_first()), "aebcd");
+        assert_eq!(b.parent(), Some(e.clone()));
+        assert_eq!(nodes[3].depth(), 3);
+        assert_eq!(values(&nodes[3].path_to_root()), "dbea");
+    }
+
+    #[test]
+    fn traversal_order() {
+        let (root, _nodes) = sample();
+        assert_eq!(values(&root.depth_first()), "abcde");
+        assert_eq!(values(&root.breadth_first()), "abecd");
+        assert_eq!(values(&root.children()), "be");
+    }
+}
@@ -0,0 +1,295 @@
+// Math that reports failures as values instead of returning NaN or an
+// infinity, or overflowing.
+//
+// Every error says which operation failed and on which input. Domain
+// errors, like the logarithm of a negative number, are always errors.
+// Whether NaN and infinite inputs and results are errors too is up to the
+// `Policy` of the `Checker` in use. The free functions use `Policy::Error`.
+
+use std::fmt;
+
+// How close `cos(x)` may get to zero before `tan(x)` is refused. The f64
+// closest to π/2 has a cosine of about 6e-17, so without this `tan` would
+// happily return 1.6e16 for what is meant to be a pole.
+pub const POLE_TOLERANCE: f64 = 1e-10;
+
+// The operation that failed, with its inputs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Div { x: f64, y: f64 },
+    Sqrt(f64),
+    Ln(f64),
+    Log { x: f64, base: f64 },
+    Pow { base: f64, exp: f64 },
+    Asin(f64),
+    Acos(f64),
+    Tan(f64),
+    Factorial(u64),
+    Binomial { n: u64, k: u64 },
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Op::Div { x, y } => write!(f, "{} / {}", x, y),
+            Op::Sqrt(x) => write!(f, "sqrt({})", x),
+            Op::Ln(x) => write!(f, "ln({})", x),
+            Op::Log { x, base } => write!(f, "log({}, base {})", x, base),
+            Op::Pow { base, exp } => write!(f, "pow({}, {})", base, exp),
+            Op::Asin(x) => write!(f, "asin({})", x),
+            Op::Acos(x) => write!(f, "acos({})", x),
+            Op::Tan(x) => write!(f, "tan({})", x),
+            Op::Factorial(n) => write!(f, "{}!", n),
+            Op::Binomial { n, k } => write!(f, "C({}, {})", n, k),
+        }
+    }
+}
+
+// Mathematical "errors" we want to catch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathErrorKind {
+    DivisionByZero,
+    NonPositiveLogarithm,
+    NegativeSquareRoot,
+    // The base of a logarithm must be positive and not 1
+    InvalidLogBase,
+    // A negative number to a fractional power has no real value
+    ComplexResult,
+    // `asin` and `acos` are only defined on [-1, 1]
+    OutOfDomain,
+    // `tan` at an odd multiple of π/2, see `POLE_TOLERANCE`
+    Pole,
+    // The result is too large for its type
+    Overflow,
+    // A NaN input or result, under `Policy::Error`
+    NotANumber,
+    // An infinite input, under `Policy::Error`
+    Infinite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MathError {
+    pub op: Op,
+    pub kind: MathErrorKind,
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self.kind {
+            MathErrorKind::DivisionByZero => "division by zero",
+            MathErrorKind::NonPositiveLogarithm => "logarithm of non-positive number",
+            MathErrorKind::NegativeSquareRoot => "square root of negative number",
+            MathErrorKind::InvalidLogBase => "logarithm base must be positive and not 1",
+            MathErrorKind::ComplexResult => "negative number to a fractional power",
+            MathErrorKind::OutOfDomain => "argument outside of [-1, 1]",
+            MathErrorKind::Pole => "argument at a pole",
+            MathErrorKind::Overflow => "result too large",
+            MathErrorKind::NotANumber => "not a number",
+            MathErrorKind::Infinite => "infinite argument",
+        };
+        write!(f, "{}: {}", self.op, reason)
+    }
+}
+
+impl std::error::Error for MathError {}
+
+pub type MathResult = Result<f64, MathError>;
+
+// What to do with NaN and infinities, in the input or in the result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    // Reject them with `NotANumber`, `Infinite` or `Overflow`
+    Error,
+    // Let them through, as plain `f64` arithmetic would
+    PassThrough,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checker {
+    pub policy: Policy,
+}
+
+const STRICT: Checker = Checker {
+    policy: Policy::Error,
+};
+
+impl Checker {
+    pub fn new(policy: Policy) -> Checker {
+        Checker { policy }
+    }
+
+    pub fn div(&self, x: f64, y: f64) -> MathResult {
+        let op = Op::Div { x, y };
+        self.check_inputs(op, &[x, y])?;
+        if y == 0.0 {
+            // This operation would `fail`, instead let's return the reason
+            // of the failure wrapped in `Err`
+            return Err(error(op, MathErrorKind::DivisionByZero));
+        }
+        self.check_result(op, x / y)
+    }
+
+    pub fn sqrt(&self, x: f64) -> MathResult {
+        let op = Op::Sqrt(x);
+        self.check_inputs(op, &[x])?;
+        if x < 0.0 {
+            return Err(error(op, MathErrorKind::NegativeSquareRoot));
+        }
+        self.check_result(op, x.sqrt())
+    }
+
+    // `x <= 0.0`, not `x < 0.0`: ln(0) is -inf, not a number
+    pub fn ln(&self, x: f64) -> MathResult {
+        let op = Op::Ln(x);
+        self.check_inputs(op, &[x])?;
+        if x <= 0.0 {
+            return Err(error(op, MathErrorKind::NonPositiveLogarithm));
+        }
+        self.check_result(op, x.ln())
+    }
+
+    pub fn log(&self, x: f64, base: f64) -> MathResult {
+        let op = Op::Log { x, base };
+        self.check_inputs(op, &[x, base])?;
+        if base <= 0.0 || base == 1.0 {
+            return Err(error(op, MathErrorKind::InvalidLogBase));
+        }
+        if x <= 0.0 {
+            return Err(error(op, MathErrorKind::NonPositiveLogarithm));
+        }
+        self.check_result(op, x.log(base))
+    }
+
+    pub fn pow(&self, base: f64, exp: f64) -> MathResult {
+        let op = Op::Pow { base, exp };
+        self.check_inputs(op, &[base, exp])?;
+        if base == 0.0 && exp < 0.0 {
+            return Err(error(op, MathErrorKind::DivisionByZero));
+        }
+        // An infinite exponent has no fractional part to speak of
+        if base < 0.0 && exp.is_finite() && exp.fract() != 0.0 {
+            return Err(error(op, MathErrorKind::ComplexResult));
+        }
+        self.check_result(op, base.powf(exp))
+    }
+
+    pub fn asin(&self, x: f64) -> MathResult {
+        let op = Op::Asin(x);
+        self.check_inputs(op, &[x])?;
+        // Written so that NaN passes, for `Policy::PassThrough`
+        if x.abs() > 1.0 {
+            return Err(error(op, MathErrorKind::OutOfDomain));
+        }
+        self.check_result(op, x.asin())
+    }
+
+    pub fn acos(&self, x: f64) -> MathResult {
+        let op = Op::Acos(x);
+        self.check_inputs(op, &[x])?;
+        // Written so that NaN passes, for `Policy::PassThrough`
+        if x.abs() > 1.0 {
+            return Err(error(op, MathErrorKind::OutOfDomain));
+        }
+        self.check_result(op, x.acos())
+    }
+
+    pub fn tan(&self, x: f64) -> MathResult {
+        let op = Op::Tan(x);
+        self.check_inputs(op, &[x])?;
+        if x.cos().abs() < POLE_TOLERANCE {
+            return Err(error(op, MathErrorKind::Pole));
+        }
+        self.check_result(op, x.tan())
+    }
+
+    fn check_inputs(&self, op: Op, inputs: &[f64]) -> Result<(), MathError> {
+        if self.policy == Policy::PassThrough {
+            return Ok(());
+        }
+        for x in inputs {
+            if x.is_nan() {
+                return Err(error(op, MathErrorKind::NotANumber));
+            }
+            if x.is_infinite() {
+                return Err(error(op, MathErrorKind::Infinite));
+            }
+        }
+        Ok(())
+    }
+
+    // Finite inputs can still give a non-finite result, e.g. 1e300 / 1e-300
+    fn check_result(&self, op: Op, result: f64) -> MathResult {
+        match self.policy {
+            Policy::PassThrough => Ok(result),
+            Policy::Error if result.is_nan() => Err(error(op, MathErrorKind::NotANumber)),
+            Policy::Error if result.is_infinite() => Err(error(op, MathErrorKind::Overflow)),
+            Policy::Error => Ok(result),
+        }
+    }
+}
+
+fn error(op: Op, kind: MathErrorKind) -> MathError {
+    MathError { op, kind }
+}
+
+pub fn div(x: f64, y: f64) -> MathResult {
+    STRICT.div(x, y)
+}
+
+pub fn sqrt(x: f64) -> MathResult {
+    STRICT.sqrt(x)
+}
+
+pub fn ln(x: f64) -> MathResult {
+    STRICT.ln(x)
+}
+
+pub fn log(x: f64, base: f64) -> MathResult {
+    STRICT.log(x, base)
+}
+
+pub fn pow(base: f64, exp: f64) -> MathResult {
+    STRICT.pow(base, exp)
+}
+
+pub fn asin(x: f64) -> MathResult {
+    STRICT.asin(x)
+}
+
+pub fn acos(x: f64) -> MathResult {
+    STRICT.acos(x)
+}
+
+pub fn tan(x: f64) -> MathResult {
+    STRICT.tan(x)
+}
+
+// `n!`, or `Overflow` from 21! on
+pub fn checked_factorial(n: u64) -> Result<u64, MathError> {
+    (2..=n)
+        .try_fold(1u64, |acc, i| acc.checked_mul(i))
+        .ok_or(error(Op::Factorial(n), MathErrorKind::Overflow))
+}
+
+// The number of ways to choose `k` items out of `n`, 0 if `k > n`, or
+// `Overflow` if that doesn't fit in a `u64`
+pub fn binomial(n: u64, k: u64) -> Result<u64, MathError> {
+    let op = Op::Binomial { n, k };
+    if k > n {
+        return Ok(0);
+    }
+    // C(n, k) == C(n, n - k), and the smaller one takes fewer steps
+    let k = k.min(n - k);
+
+    // After step `i` the result is C(n - k + i, i), an integer, and it
+    // only grows. Multiplying before dividing keeps it exact, and `u128`
+    // holds the product of a `u64` result and a `u64` factor.
+    let mut result: u128 = 1;
+    for i in 1..=k as u128 {
+        result = result * (n as u128 - k as u128 + i) / i;
+        if result > u64::MAX as u128 {
+            return Err(error(op, MathErrorKind::Overflow));
+        }
+    }
+    Ok(result as u64)
+}
@@ -0,0 +1,3 @@
+// The `checked` module is a library so that other examples, like
+// `question_mark_operator`, can use it too
+pub mod checked;
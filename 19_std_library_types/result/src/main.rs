@@ -1,51 +1,15 @@
-mod checked {
-    // Mathematical "errors" we want to catch
-    #[derive(Debug)]
-    pub enum MathError {
-        DivisionByZero,
-        NonPositiveLogarith,
-        NegativeSquareRoot,
-    }
-
-    pub type MathResult = Result<f64, MathError>;
-
-    pub fn div(x: f64, y: f64) -> MathResult {
-        if y == 0.0 {
-            // This operation would `fail`, instead let's return the reason
-            // of the failure wrapped in `Err`
-            Err(MathError::DivisionByZero)
-        } else {
-            // This operation is valid, return the result wrapped in `Ok`
-            Ok(x / y)
-        }
-    }
-
-    pub fn sqrt(x: f64) -> MathResult {
-        if x < 0.0 {
-            Err(MathError::NegativeSquareRoot)
-        } else {
-            Ok(x.sqrt())
-        }
-    }
-
-    pub fn ln(x: f64) -> MathResult {
-        if x < 0.0 {
-            Err(MathError::NonPositiveLogarith)
-        } else {
-            Ok(x.ln())
-        }
-    }
-}
+use result::checked::{self, Checker, MathErrorKind, Policy};
+use std::f64::consts::FRAC_PI_2;
 
 // `op(x, y)` == `sqrt(ln(x /y))`
 fn op(x: f64, y: f64) -> f64 {
     // This is a tree leven match pyramid
     match checked::div(x, y) {
-        Err(why) => panic!("{:?}", why),
+        Err(why) => panic!("{}", why),
         Ok(ratio) => match checked::ln(ratio) {
-            Err(why) => panic!("{:?}", why),
+            Err(why) => panic!("{}", why),
             Ok(ln) => match checked::sqrt(ln) {
-                Err(why) => panic!("{:?}", why),
+                Err(why) => panic!("{}", why),
                 Ok(sqrt) => sqrt,
             },
         },
@@ -53,6 +17,42 @@ fn op(x: f64, y: f64) -> f64 {
 }
 
 fn main() {
-    // Fail: MathError:: NegativeSquareRoot
+    // Every error names the operation and the input that caused it
+    let results = [
+        checked::ln(0.0),
+        checked::log(8.0, 2.0),
+        checked::log(8.0, 1.0),
+        checked::pow(-8.0, 1.0 / 3.0),
+        checked::pow(10.0, 400.0),
+        checked::asin(1.5),
+        checked::acos(-1.0),
+        checked::tan(FRAC_PI_2),
+        checked::tan(1.0),
+    ];
+    for result in results {
+        match result {
+            Ok(value) => println!("Ok: {}", value),
+            Err(why) => println!("Err: {}", why),
+        }
+    }
+
+    // The error is a value, so callers can branch on its kind
+    if let Err(why) = checked::sqrt(f64::NAN) {
+        assert_eq!(why.kind, MathErrorKind::NotANumber);
+    }
+
+    // With `PassThrough`, NaN and infinities behave as in plain `f64`
+    // arithmetic. Domain errors are still errors.
+    let lenient = Checker::new(Policy::PassThrough);
+    println!("Lenient 10^400: {:?}", lenient.pow(10.0, 400.0));
+    println!("Lenient sqrt(NaN): {:?}", lenient.sqrt(f64::NAN));
+    println!("Lenient ln(-1): {:?}", lenient.ln(-1.0).map_err(|e| e.kind));
+
+    println!("20! = {:?}", checked::checked_factorial(20));
+    println!("21! = {}", checked::checked_factorial(21).unwrap_err());
+    println!("C(52, 5) = {:?}", checked::binomial(52, 5));
+    println!("C(100, 50) = {}", checked::binomial(100, 50).unwrap_err());
+
+    // Fail: sqrt(-2.30...): square root of negative number
     println!("{}", op(1.0, 10.0));
 }
@@ -1,3 +1,8 @@
+mod text;
+
+use std::env;
+use text::{Alphabet, Normalization, Report};
+
 fn main() {
     // (all the type annotations are superfluous)
     // A reference to a string allocated in read only memory
@@ -35,4 +40,55 @@ fn main() {
     let bob: String = alice.replace("cat", "dog");
     println!("Alice says: {}", alice);
     println!("Bob says: {}", bob);
+
+    // `char`s are not what a reader sees as characters
+    let cafe = "cafe\u{301} 👨‍👩‍👧";
+    println!(
+        "{}: {} chars, {} graphemes, reversed by char {}, by grapheme {}",
+        cafe,
+        cafe.chars().count(),
+        text::grapheme_len(cafe),
+        cafe.chars().rev().collect::<String>(),
+        text::reverse(cafe)
+    );
+
+    // Equal-looking strings only compare equal once normalized
+    let (composed, decomposed) = ("Café", "CAFE\u{301}");
+    println!(
+        "{} == {}: {} as is, {} normalized",
+        composed,
+        decomposed,
+        composed == decomposed,
+        text::eq_ignore_case(composed, decomposed, Normalization::Nfc)
+    );
+    println!(
+        "Straße == STRASSE: {}, x² == X2: NFC {}, NFKC {}",
+        text::eq_ignore_case("Straße", "STRASSE", Normalization::Nfc),
+        text::eq_ignore_case("x²", "X2", Normalization::Nfc),
+        text::eq_ignore_case("x²", "X2", Normalization::Nfkc)
+    );
+
+    let english = Alphabet::english();
+    println!("Pangram? {}", english.is_pangram(pangram));
+    println!("Missing from Bob: {:?}", english.missing(&bob));
+
+    let greek = "Ξεσκεπάζω την ψυχοφθόρα βδελυγμία";
+    println!(
+        "Greek pangram? {}, missing {:?}",
+        Alphabet::greek().is_pangram(greek),
+        Alphabet::greek().missing(greek)
+    );
+
+    println!(
+        "Word counts: {:?}",
+        &text::word_histogram("The cat saw the other CAT.")[..2]
+    );
+
+    // `strings FILE...` reports on each file instead
+    for path in env::args().skip(1) {
+        match Report::from_file(&path, &english) {
+            Ok(report) => println!("--- {} ---\n{}", path, report),
+            Err(e) => eprintln!("{}: {}", path, e),
+        }
+    }
 }
@@ -0,0 +1,199 @@
+// Text statistics that hold up outside of ASCII.
+//
+// A `char` is a Unicode scalar value, not what a reader sees as one
+// character: "é" may be `e` followed by a combining accent, and a family
+// emoji is several `char`s joined by zero-width joiners. Lengths and
+// reversal here work on grapheme clusters instead, and comparisons
+// normalize both sides first so that equal-looking text compares equal.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalization {
+    // Canonical: "e" + U+0301 and "é" are the same
+    Nfc,
+    // Compatibility as well: "ﬁ" is "fi" and "①" is "1"
+    Nfkc,
+}
+
+pub fn normalize(text: &str, form: Normalization) -> String {
+    match form {
+        Normalization::Nfc => text.nfc().collect(),
+        Normalization::Nfkc => text.nfkc().collect(),
+    }
+}
+
+// Normalizes and removes case differences. The standard library has no
+// case folding, but upper-casing before lower-casing gets close: "ß" and
+// "SS" both end up as "ss". Lower-casing turns a word-final "Σ" into "ς",
+// which folding maps back to "σ". Changing case can produce unnormalized
+// text, hence the second pass.
+pub fn fold(text: &str, form: Normalization) -> String {
+    let folded = normalize(text, form)
+        .to_uppercase()
+        .to_lowercase()
+        .replace('ς', "σ");
+    normalize(&folded, form)
+}
+
+pub fn eq_ignore_case(a: &str, b: &str, form: Normalization) -> bool {
+    fold(a, form) == fold(b, form)
+}
+
+// Number of user-perceived characters
+pub fn grapheme_len(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+// Reverses grapheme clusters, so accents stay on their letters and emoji
+// sequences stay whole. `text.chars().rev()` would tear both apart.
+pub fn reverse(text: &str) -> String {
+    text.graphemes(true).rev().collect()
+}
+
+// The letters a pangram must contain. Letters are stored folded, so an
+// alphabet matches text regardless of case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    letters: BTreeSet<String>,
+}
+
+impl Alphabet {
+    // Every grapheme of `letters` other than whitespace is a letter
+    pub fn new(letters: &str) -> Alphabet {
+        Alphabet {
+            letters: fold(letters, Normalization::Nfc)
+                .graphemes(true)
+                .filter(|g| !g.trim().is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    pub fn english() -> Alphabet {
+        Alphabet::new("abcdefghijklmnopqrstuvwxyz")
+    }
+
+    // Final sigma is folded to σ, so it needs no entry of its own
+    pub fn greek() -> Alphabet {
+        Alphabet::new("αβγδεζηθικλμνξοπρστυφχψω")
+    }
+
+    // Letters of the alphabet that don't occur in `text`, in order. A
+    // letter with diacritics also counts as its base letter, so "ί" covers
+    // "ι", while an alphabet that lists "é" still needs an actual "é".
+    pub fn missing(&self, text: &str) -> Vec<&str> {
+        let folded = fold(text, Normalization::Nfc);
+        let mut present: BTreeSet<String> = BTreeSet::new();
+        for g in folded.graphemes(true) {
+            present.insert(g.to_string());
+            present.extend(g.nfd().next().map(String::from));
+        }
+        self.letters
+            .iter()
+            .filter(|letter| !present.contains(*letter))
+            .map(String::as_str)
+            .collect()
+    }
+
+    pub fn is_pangram(&self, text: &str) -> bool {
+        self.missing(text).is_empty()
+    }
+}
+
+// How often each letter occurs, ignoring case. A letter is a grapheme
+// starting with an alphabetic `char`, so "é" counts as itself, not as "e".
+pub fn letter_histogram(text: &str) -> BTreeMap<String, usize> {
+    let mut histogram = BTreeMap::new();
+    for g in fold(text, Normalization::Nfc).graphemes(true) {
+        if g.chars().next().is_some_and(char::is_alphabetic) {
+            *histogram.entry(g.to_string()).or_insert(0) += 1;
+        }
+    }
+    histogram
+}
+
+// How often each word occurs, ignoring case, most frequent first. Words
+// are split on Unicode word boundaries, so "don't" is one word and
+// punctuation is dropped.
+pub fn word_histogram(text: &str) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for word in fold(text, Normalization::Nfkc).unicode_words() {
+        *counts.entry(word.to_string()).or_insert(0) += 1;
+    }
+
+    let mut histogram: Vec<(String, usize)> = counts.into_iter().collect();
+    // Ties in alphabetical order, so the output is stable
+    histogram.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    histogram
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub lines: usize,
+    pub words: usize,
+    pub chars: usize,
+    pub graphemes: usize,
+    pub missing: Vec<String>,
+    pub letters: BTreeMap<String, usize>,
+    pub words_by_count: Vec<(String, usize)>,
+}
+
+impl Report {
+    pub fn new(text: &str, alphabet: &Alphabet) -> Report {
+        let words_by_count = word_histogram(text);
+        Report {
+            lines: text.lines().count(),
+            words: words_by_count.iter().map(|(_, n)| n).sum(),
+            chars: text.chars().count(),
+            graphemes: grapheme_len(text),
+            missing: alphabet
+                .missing(text)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            letters: letter_histogram(text),
+            words_by_count,
+        }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P, alphabet: &Alphabet) -> io::Result<Report> {
+        Ok(Report::new(&fs::read_to_string(path)?, alphabet))
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} line(s), {} word(s), {} char(s), {} grapheme(s)",
+            self.lines, self.words, self.chars, self.graphemes
+        )?;
+        if self.missing.is_empty() {
+            writeln!(f, "pangram: yes")?;
+        } else {
+            writeln!(f, "pangram: no, missing {}", self.missing.concat())?;
+        }
+
+        // A bar per letter, scaled so that the most frequent one gets 40
+        let max = self.letters.values().copied().max().unwrap_or(0);
+        for (letter, &count) in &self.letters {
+            let width = (count * 40).div_ceil(max);
+            writeln!(f, "{} {:>5} {}", letter, count, "#".repeat(width))?;
+        }
+
+        let top: Vec<String> = self
+            .words_by_count
+            .iter()
+            .take(10)
+            .map(|(word, count)| format!("{} ({})", word, count))
+            .collect();
+        write!(f, "top words: {}", top.join(", "))
+    }
+}
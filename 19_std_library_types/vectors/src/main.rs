@@ -1,3 +1,8 @@
+mod ring;
+
+use ring::{Full, Policy, RingBuffer};
+use std::rc::Rc;
+
 fn main() {
     // Iterators can be collected into vectors
     let collected_iterator: Vec<i32> = (0..10).collect();
@@ -45,4 +50,59 @@ fn main() {
         *x *= 3;
     }
     println!("Updated vector: {:?}", xs);
+
+    // A `Vec` grows without bound. For a rolling window of the last few
+    // samples, a `RingBuffer` keeps a fixed capacity instead.
+    let samples = [3.0, 5.0, 4.0, 8.0, 6.0, 7.0, 9.0];
+    let mut window = RingBuffer::new(3, Policy::Overwrite);
+    for sample in samples {
+        let evicted = window.push_back(sample).unwrap();
+        let average = window.iter().sum::<f64>() / window.len() as f64;
+        println!(
+            "Window: {:?}, evicted {:?}, moving average {:.2}",
+            window, evicted, average
+        );
+    }
+
+    // Indexing starts at the oldest element, wherever it's stored
+    println!(
+        "Oldest: {}, newest: {}",
+        window[0],
+        window[window.len() - 1]
+    );
+    window[1] *= 10.0;
+    println!(
+        "Newest to oldest: {:?}",
+        window.iter().rev().collect::<Vec<_>>()
+    );
+
+    // The elements wrapped around the end of the storage, so they come
+    // back as two slices, without copying
+    let (first, second) = window.as_slices();
+    println!("As slices: {:?} and {:?}", first, second);
+
+    // A rejecting buffer hands the value back instead of dropping the oldest
+    let mut queue = RingBuffer::new(2, Policy::Reject);
+    queue.extend(["a", "b"]);
+    if let Err(Full(value)) = queue.push_back("c") {
+        println!("Queue {:?} is full, {} was rejected", queue, value);
+    }
+    println!(
+        "Popped {:?}, then {:?}",
+        queue.pop_front(),
+        queue.pop_back()
+    );
+
+    // Overwritten, popped and remaining elements are each dropped once
+    let counted = Rc::new(());
+    let mut buffer = RingBuffer::new(4, Policy::Overwrite);
+    buffer.extend((0..10).map(|_| Rc::clone(&counted)));
+    buffer.pop_front();
+    println!(
+        "References held by the buffer: {}",
+        Rc::strong_count(&counted) - 1
+    );
+    let last_two: Vec<_> = buffer.into_iter().rev().take(2).collect();
+    drop(last_two);
+    assert_eq!(Rc::strong_count(&counted), 1);
 }
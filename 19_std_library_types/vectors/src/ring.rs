@@ -0,0 +1,392 @@
+// A fixed-capacity FIFO queue, for rolling windows of samples.
+//
+// The elements live in one allocation made up front and never grown. `head`
+// is the slot of the oldest element, and the others follow it, wrapping
+// around to slot 0 at the end. Once the buffer is full, pushing either
+// overwrites the oldest element or is rejected, depending on the policy.
+//
+// The slots are `MaybeUninit<T>` because only `len` of them hold a value
+// at any time. Every `unsafe` block relies on one invariant: the slots
+// `head, head + 1, ..., head + len - 1` (modulo the capacity) are
+// initialized and all the others are not.
+
+use std::fmt;
+use std::iter::FusedIterator;
+use std::mem::MaybeUninit;
+use std::ops::{Index, IndexMut};
+use std::slice;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    // A push into a full buffer drops the oldest element
+    Overwrite,
+    // A push into a full buffer fails and hands the value back
+    Reject,
+}
+
+// Returned by `push_back` when a `Reject` buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Full<T>(pub T);
+
+impl<T> fmt::Display for Full<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ring buffer is full")
+    }
+}
+
+pub struct RingBuffer<T> {
+    slots: Box<[MaybeUninit<T>]>,
+    head: usize,
+    len: usize,
+    policy: Policy,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize, policy: Policy) -> RingBuffer<T> {
+        assert!(
+            capacity > 0,
+            "a ring buffer needs room for at least one element"
+        );
+        RingBuffer {
+            slots: (0..capacity).map(|_| MaybeUninit::uninit()).collect(),
+            head: 0,
+            len: 0,
+            policy,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity()
+    }
+
+    pub fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    // Appends `value` as the newest element. With `Policy::Overwrite` a full
+    // buffer returns the oldest element, which the push evicted.
+    pub fn push_back(&mut self, value: T) -> Result<Option<T>, Full<T>> {
+        if !self.is_full() {
+            let slot = self.slot(self.len);
+            self.slots[slot].write(value);
+            self.len += 1;
+            return Ok(None);
+        }
+
+        match self.policy {
+            Policy::Reject => Err(Full(value)),
+            Policy::Overwrite => {
+                // The newest element goes where the oldest one was
+                let old = std::mem::replace(&mut self.slots[self.head], MaybeUninit::new(value));
+                self.head = self.slot(1);
+                // Safety: the buffer is full, so the old head was initialized
+                Ok(Some(unsafe { old.assume_init() }))
+            }
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let slot = self.head;
+        self.head = self.slot(1);
+        self.len -= 1;
+        // Safety: `slot` held the oldest element and is now outside of the
+        // initialized range, so it won't be read or dropped again
+        Some(unsafe { self.slots[slot].assume_init_read() })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.len -= 1;
+        let slot = self.slot(self.len);
+        // Safety: as in `pop_front`, for the newest element
+        Some(unsafe { self.slots[slot].assume_init_read() })
+    }
+
+    // Index 0 is the oldest element
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        // Safety: the first `len` elements from `head` are initialized
+        Some(unsafe { self.slots[self.slot(index)].assume_init_ref() })
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        let slot = self.slot(index);
+        // Safety: as in `get`
+        Some(unsafe { self.slots[slot].assume_init_mut() })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|last| self.get(last))
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+        self.head = 0;
+    }
+
+    // The elements in order, oldest first, as two slices: from the oldest
+    // element to the end of the allocation, then whatever wrapped around.
+    // The second slice is empty unless the elements wrap.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (first, second) = self.ranges();
+        let slots = &self.slots;
+        // Safety: both ranges cover initialized slots only, and
+        // `MaybeUninit<T>` has the same layout as `T`
+        unsafe {
+            (
+                &*(&slots[first.0..first.1] as *const [MaybeUninit<T>] as *const [T]),
+                &*(&slots[second.0..second.1] as *const [MaybeUninit<T>] as *const [T]),
+            )
+        }
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (first, second) = self.ranges();
+        // The second range always ends before the first one starts
+        let (front, back) = self.slots.split_at_mut(first.0);
+        // Safety: as in `as_slices`
+        unsafe {
+            (
+                &mut *(&mut back[..first.1 - first.0] as *mut [MaybeUninit<T>] as *mut [T]),
+                &mut *(&mut front[second.0..second.1] as *mut [MaybeUninit<T>] as *mut [T]),
+            )
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (first, second) = self.as_slices();
+        Iter {
+            first: first.iter(),
+            second: second.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (first, second) = self.as_mut_slices();
+        IterMut {
+            first: first.iter_mut(),
+            second: second.iter_mut(),
+        }
+    }
+
+    // The slot of the element `offset` places after the oldest one
+    fn slot(&self, offset: usize) -> usize {
+        (self.head + offset) % self.capacity()
+    }
+
+    // The slot ranges, as `(start, end)`, that `as_slices` returns
+    fn ranges(&self) -> ((usize, usize), (usize, usize)) {
+        let end = self.head + self.len;
+        if end <= self.capacity() {
+            ((self.head, end), (0, 0))
+        } else {
+            ((self.head, self.capacity()), (0, end - self.capacity()))
+        }
+    }
+}
+
+impl<T> Drop for RingBuffer<T> {
+    fn drop(&mut self) {
+        let (first, second) = self.as_mut_slices();
+        // Safety: the slices hold exactly the initialized elements, and the
+        // slots are never touched again after this
+        unsafe {
+            std::ptr::drop_in_place(first);
+            std::ptr::drop_in_place(second);
+        }
+    }
+}
+
+impl<T: Clone> Clone for RingBuffer<T> {
+    // The clone is laid out from slot 0, whatever the original's head
+    fn clone(&self) -> Self {
+        let mut clone = RingBuffer::new(self.capacity(), self.policy);
+        for value in self {
+            let _ = clone.push_back(value.clone());
+        }
+        clone
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RingBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for RingBuffer<T> {
+    // Compares the elements in order, not the capacity or the policy
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T> Index<usize> for RingBuffer<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        let len = self.len;
+        self.get(index).unwrap_or_else(|| {
+            panic!(
+                "index {} out of range for ring buffer of length {}",
+                index, len
+            )
+        })
+    }
+}
+
+impl<T> IndexMut<usize> for RingBuffer<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len;
+        self.get_mut(index).unwrap_or_else(|| {
+            panic!(
+                "index {} out of range for ring buffer of length {}",
+                index, len
+            )
+        })
+    }
+}
+
+// Pushes every element, so an `Overwrite` buffer keeps the last
+// `capacity` of them and a `Reject` buffer the first `capacity`
+impl<T> Extend<T> for RingBuffer<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            let _ = self.push_back(value);
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    first: slice::Iter<'a, T>,
+    second: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.first.next().or_else(|| self.second.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.first.len() + self.second.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.second.next_back().or_else(|| self.first.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+pub struct IterMut<'a, T> {
+    first: slice::IterMut<'a, T>,
+    second: slice::IterMut<'a, T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.first.next().or_else(|| self.second.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.first.len() + self.second.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        self.second.next_back().or_else(|| self.first.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+// Takes the elements out of the buffer, oldest first
+pub struct IntoIter<T>(RingBuffer<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for RingBuffer<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a RingBuffer<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut RingBuffer<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
@@ -0,0 +1,649 @@
+// A small declarative argument parser.
+//
+// A `Command` describes the flags, options, positional arguments and
+// subcommands a program accepts. `Command::parse` walks the raw arguments
+// once and produces `Matches`, from which typed values are pulled out
+// through `FromStr`. The same description is used to generate `--help`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    // `--help` or `-h` was passed, carries the rendered help text
+    Help(String),
+    UnknownCommand {
+        name: String,
+        suggestion: Option<String>,
+    },
+    UnknownFlag {
+        flag: String,
+        suggestion: Option<String>,
+    },
+    MissingValue(String),
+    MissingArgument(String),
+    UnexpectedArgument(String),
+    UnexpectedValue(String),
+    InvalidValue {
+        name: String,
+        value: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Help(text) => write!(f, "{}", text),
+            Error::UnknownCommand { name, suggestion } => {
+                write!(f, "unrecognized command '{}'", name)?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, ", did you mean '{}'?", suggestion)?;
+                }
+                Ok(())
+            }
+            Error::UnknownFlag { flag, suggestion } => {
+                write!(f, "unrecognized flag '{}'", flag)?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, ", did you mean '{}'?", suggestion)?;
+                }
+                Ok(())
+            }
+            Error::MissingValue(flag) => write!(f, "flag '{}' expects a value", flag),
+            Error::MissingArgument(name) => write!(f, "missing required argument <{}>", name),
+            Error::UnexpectedArgument(arg) => write!(f, "unexpected argument '{}'", arg),
+            Error::UnexpectedValue(flag) => write!(f, "flag '{}' does not take a value", flag),
+            Error::InvalidValue {
+                name,
+                value,
+                reason,
+            } => write!(f, "invalid value '{}' for <{}>: {}", value, name, reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Arg {
+    name: String,
+    long: Option<String>,
+    short: Option<char>,
+    help: String,
+    takes_value: bool,
+    required: bool,
+    default: Option<String>,
+    // Also accepted after any subcommand, and reported on this command's
+    // `Matches`
+    global: bool,
+}
+
+impl Arg {
+    // A boolean switch such as `--verbose`
+    pub fn flag(name: &str) -> Arg {
+        Arg {
+            name: name.to_string(),
+            long: Some(name.to_string()),
+            short: None,
+            help: String::new(),
+            takes_value: false,
+            required: false,
+            default: None,
+            global: false,
+        }
+    }
+
+    // A flag followed by a value: `--by 2`, `--by=2`, `-b 2` or `-b2`
+    pub fn option(name: &str) -> Arg {
+        Arg {
+            takes_value: true,
+            ..Arg::flag(name)
+        }
+    }
+
+    // A required argument identified by its position
+    pub fn positional(name: &str) -> Arg {
+        Arg {
+            long: None,
+            takes_value: true,
+            required: true,
+            ..Arg::flag(name)
+        }
+    }
+
+    pub fn short(mut self, short: char) -> Arg {
+        self.short = Some(short);
+        self
+    }
+
+    pub fn help(mut self, help: &str) -> Arg {
+        self.help = help.to_string();
+        self
+    }
+
+    pub fn optional(mut self) -> Arg {
+        self.required = false;
+        self
+    }
+
+    // Lets the flag or option appear after a subcommand too, as in
+    // `increase 5 -v`
+    pub fn global(mut self) -> Arg {
+        self.global = true;
+        self
+    }
+
+    // A default also makes the argument optional
+    pub fn default(mut self, value: &str) -> Arg {
+        self.default = Some(value.to_string());
+        self.required = false;
+        self
+    }
+
+    fn is_positional(&self) -> bool {
+        self.long.is_none() && self.short.is_none()
+    }
+
+    fn usage(&self) -> String {
+        match (self.is_positional(), self.required) {
+            (true, true) => format!("<{}>", self.name),
+            (true, false) => format!("[{}]", self.name),
+            (false, _) => {
+                let mut usage = match (&self.long, self.short) {
+                    (Some(long), Some(short)) => format!("-{}, --{}", short, long),
+                    (Some(long), None) => format!("    --{}", long),
+                    (None, Some(short)) => format!("-{}", short),
+                    (None, None) => unreachable!(),
+                };
+                if self.takes_value {
+                    usage.push_str(&format!(" <{}>", self.name));
+                }
+                usage
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Command {
+    name: String,
+    about: String,
+    args: Vec<Arg>,
+    subcommands: Vec<Command>,
+}
+
+impl Command {
+    pub fn new(name: &str) -> Command {
+        Command {
+            name: name.to_string(),
+            about: String::new(),
+            args: Vec::new(),
+            subcommands: Vec::new(),
+        }
+    }
+
+    pub fn about(mut self, about: &str) -> Command {
+        self.about = about.to_string();
+        self
+    }
+
+    pub fn arg(mut self, arg: Arg) -> Command {
+        self.args.push(arg);
+        self
+    }
+
+    pub fn subcommand(mut self, subcommand: Command) -> Command {
+        self.subcommands.push(subcommand);
+        self
+    }
+
+    // Parses arguments as returned by `env::args()`, the first item being
+    // the program name
+    pub fn parse<I>(&self, args: I) -> Result<Matches, Error>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let args: Vec<String> = args.into_iter().skip(1).collect();
+        self.parse_from(&self.name, &args, &[])
+    }
+
+    // `inherited` are the global flags and options of the commands above
+    // this one
+    fn parse_from(
+        &self,
+        path: &str,
+        args: &[String],
+        inherited: &[&Arg],
+    ) -> Result<Matches, Error> {
+        let mut matches = Matches::default();
+        // Flags and options accepted here, this command's own first
+        let named: Vec<&Arg> = self
+            .args
+            .iter()
+            .filter(|a| !a.is_positional())
+            .chain(inherited.iter().copied())
+            .collect();
+        let positionals: Vec<&Arg> = self.args.iter().filter(|a| a.is_positional()).collect();
+        let mut next_positional = 0;
+        let mut only_positionals = false;
+        let mut i = 0;
+
+        while i < args.len() {
+            let arg = &args[i];
+            i += 1;
+
+            if !only_positionals && arg == "--" {
+                only_positionals = true;
+                continue;
+            }
+
+            if !only_positionals && (arg == "--help" || arg == "-h") {
+                return Err(Error::Help(self.render_help(path, inherited)));
+            }
+
+            if !only_positionals && arg.starts_with("--") {
+                let (name, inline) = match arg[2..].split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (&arg[2..], None),
+                };
+                let spec = named
+                    .iter()
+                    .find(|a| a.long.as_deref() == Some(name))
+                    .ok_or_else(|| Error::UnknownFlag {
+                        flag: arg.clone(),
+                        suggestion: suggest_flag(name, &named),
+                    })?;
+                let value = match inline {
+                    Some(_) if !spec.takes_value => {
+                        return Err(Error::UnexpectedValue(format!("--{}", name)))
+                    }
+                    Some(value) => Some(value),
+                    None if spec.takes_value => {
+                        let value = args
+                            .get(i)
+                            .ok_or_else(|| Error::MissingValue(format!("--{}", name)))?;
+                        i += 1;
+                        Some(value.clone())
+                    }
+                    None => None,
+                };
+                matches.record(spec, value);
+                continue;
+            }
+
+            // `-5` is a negative number, unless there's a `-5` flag
+            let first_short = arg.strip_prefix('-').and_then(|rest| rest.chars().next());
+            let negative_number = first_short
+                .is_some_and(|c| c.is_ascii_digit() && !named.iter().any(|a| a.short == Some(c)));
+
+            if !only_positionals && !negative_number && arg.len() > 1 && arg.starts_with('-') {
+                // Either a cluster of switches (`-vq`) or a single short
+                // option with its value attached (`-b2`)
+                for (offset, short) in arg[1..].char_indices() {
+                    let spec = named
+                        .iter()
+                        .find(|a| a.short == Some(short))
+                        .ok_or_else(|| Error::UnknownFlag {
+                            flag: format!("-{}", short),
+                            suggestion: None,
+                        })?;
+                    if !spec.takes_value {
+                        matches.record(spec, None);
+                        continue;
+                    }
+                    let attached = &arg[1 + offset + short.len_utf8()..];
+                    let value = if !attached.is_empty() {
+                        attached.strip_prefix('=').unwrap_or(attached).to_string()
+                    } else {
+                        let value = args
+                            .get(i)
+                            .ok_or_else(|| Error::MissingValue(format!("-{}", short)))?;
+                        i += 1;
+                        value.clone()
+                    };
+                    matches.record(spec, Some(value));
+                    break;
+                }
+                continue;
+            }
+
+            // The first bare word selects a subcommand, everything after it
+            // belongs to that subcommand
+            if !only_positionals && next_positional == 0 && !self.subcommands.is_empty() {
+                if let Some(sub) = self.subcommands.iter().find(|s| s.name == *arg) {
+                    let sub_path = format!("{} {}", path, sub.name);
+                    let globals: Vec<&Arg> = named.iter().copied().filter(|a| a.global).collect();
+                    let sub_matches = sub.parse_from(&sub_path, &args[i..], &globals)?;
+                    // Global flags given after the subcommand count here too
+                    for spec in globals {
+                        if sub_matches.flags.contains(&spec.name) {
+                            matches.record(spec, None);
+                        } else if let Some(value) = sub_matches.values.get(&spec.name) {
+                            matches.record(spec, Some(value.clone()));
+                        }
+                    }
+                    matches.subcommand = Some((sub.name.clone(), Box::new(sub_matches)));
+                    break;
+                }
+                // A near miss of a known command is almost certainly a typo,
+                // even when the word could also be taken as a positional
+                let suggestion = self.suggest_command(arg);
+                if suggestion.is_some() || positionals.is_empty() {
+                    return Err(Error::UnknownCommand {
+                        name: arg.clone(),
+                        suggestion,
+                    });
+                }
+            }
+
+            let spec = positionals
+                .get(next_positional)
+                .ok_or_else(|| Error::UnexpectedArgument(arg.clone()))?;
+            next_positional += 1;
+            matches.record(spec, Some(arg.clone()));
+        }
+
+        for spec in &self.args {
+            if matches.values.contains_key(&spec.name) {
+                continue;
+            }
+            if let Some(default) = &spec.default {
+                matches.values.insert(spec.name.clone(), default.clone());
+            } else if spec.required {
+                return Err(Error::MissingArgument(spec.name.clone()));
+            }
+        }
+
+        Ok(matches)
+    }
+
+    fn suggest_command(&self, name: &str) -> Option<String> {
+        suggest(name, self.subcommands.iter().map(|s| s.name.as_str()))
+    }
+
+    fn render_help(&self, path: &str, inherited: &[&Arg]) -> String {
+        let mut help = String::new();
+        if !self.about.is_empty() {
+            help.push_str(&format!("{}\n\n", self.about));
+        }
+
+        let positionals: Vec<&Arg> = self.args.iter().filter(|a| a.is_positional()).collect();
+        let options: Vec<&Arg> = self
+            .args
+            .iter()
+            .filter(|a| !a.is_positional())
+            .chain(inherited.iter().copied())
+            .collect();
+
+        let mut usage = format!("usage: {} [OPTIONS]", path);
+        if !self.subcommands.is_empty() {
+            usage.push_str(if positionals.is_empty() {
+                " <COMMAND>"
+            } else {
+                " [COMMAND]"
+            });
+        }
+        for arg in &positionals {
+            usage.push(' ');
+            usage.push_str(&arg.usage());
+        }
+        help.push_str(&usage);
+        help.push('\n');
+
+        if !self.subcommands.is_empty() {
+            help.push_str("\ncommands:\n");
+            for sub in &self.subcommands {
+                help.push_str(&format!("    {:<20}{}\n", sub.name, sub.about));
+            }
+        }
+
+        if !positionals.is_empty() {
+            help.push_str("\narguments:\n");
+            for arg in &positionals {
+                help.push_str(&format!("    {:<20}{}\n", arg.usage(), arg.help));
+            }
+        }
+
+        help.push_str("\noptions:\n");
+        for arg in options {
+            let mut line = format!("    {:<20}{}", arg.usage(), arg.help);
+            if let Some(default) = &arg.default {
+                line.push_str(&format!(" [default: {}]", default));
+            }
+            help.push_str(&line);
+            help.push('\n');
+        }
+        help.push_str(&format!("    {:<20}{}\n", "-h, --help", "Print this help"));
+
+        help
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Matches {
+    values: HashMap<String, String>,
+    flags: HashSet<String>,
+    subcommand: Option<(String, Box<Matches>)>,
+}
+
+impl Matches {
+    pub fn is_present(&self, name: &str) -> bool {
+        self.flags.contains(name) || self.values.contains_key(name)
+    }
+
+    pub fn value_of(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    // Parses the value of `name`, which must either have been given or
+    // have a default
+    pub fn value<T>(&self, name: &str) -> Result<T, Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.try_value(name)?
+            .ok_or_else(|| Error::MissingArgument(name.to_string()))
+    }
+
+    pub fn try_value<T>(&self, name: &str) -> Result<Option<T>, Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.value_of(name)
+            .map(|value| {
+                value.parse().map_err(|e: T::Err| Error::InvalidValue {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                    reason: e.to_string(),
+                })
+            })
+            .transpose()
+    }
+
+    pub fn subcommand(&self) -> Option<(&str, &Matches)> {
+        self.subcommand
+            .as_ref()
+            .map(|(name, matches)| (name.as_str(), matches.as_ref()))
+    }
+
+    fn record(&mut self, spec: &Arg, value: Option<String>) {
+        match value {
+            Some(value) => {
+                self.values.insert(spec.name.clone(), value);
+            }
+            None => {
+                self.flags.insert(spec.name.clone());
+            }
+        }
+    }
+}
+
+fn suggest_flag(name: &str, named: &[&Arg]) -> Option<String> {
+    suggest(name, named.iter().filter_map(|a| a.long.as_deref())).map(|s| format!("--{}", s))
+}
+
+// Picks the closest candidate, if any is within a couple of edits
+fn suggest<'a, I>(input: &str, candidates: I) -> Option<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(input, candidate), candidate))
+        .filter(|&(distance, candidate)| distance <= 2 && distance < candidate.len())
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+// Levenshtein distance over chars, keeping a single row of the table
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> Command {
+        Command::new("prog")
+            .arg(Arg::positional("string").optional())
+            .arg(Arg::flag("verbose").short('v').global())
+            .arg(Arg::flag("quiet").short('q'))
+            .subcommand(
+                Command::new("increase")
+                    .arg(Arg::positional("integer"))
+                    .arg(Arg::option("by").short('b').default("1")),
+            )
+    }
+
+    fn parse(args: &[&str]) -> Result<Matches, Error> {
+        command().parse(["prog"].iter().chain(args).map(|a| a.to_string()))
+    }
+
+    fn increase(args: &[&str]) -> (i32, i32) {
+        let matches = parse(args).unwrap();
+        let (name, sub) = matches.subcommand().unwrap();
+        assert_eq!(name, "increase");
+        (sub.value("integer").unwrap(), sub.value("by").unwrap())
+    }
+
+    #[test]
+    fn flags() {
+        let matches = parse(&["-vq"]).unwrap();
+        assert!(matches.is_present("verbose"));
+        assert!(matches.is_present("quiet"));
+
+        let matches = parse(&["--quiet"]).unwrap();
+        assert!(!matches.is_present("verbose"));
+        assert!(matches.is_present("quiet"));
+    }
+
+    #[test]
+    fn positionals() {
+        let matches = parse(&["42"]).unwrap();
+        assert_eq!(matches.value_of("string"), Some("42"));
+        assert!(matches.subcommand().is_none());
+
+        // After `--`, even a subcommand's name is a positional
+        let matches = parse(&["--", "increase"]).unwrap();
+        assert_eq!(matches.value_of("string"), Some("increase"));
+
+        assert_eq!(parse(&[]).unwrap().value_of("string"), None);
+    }
+
+    #[test]
+    fn option_values() {
+        assert_eq!(increase(&["increase", "5"]), (5, 1));
+        assert_eq!(increase(&["increase", "5", "--by", "3"]), (5, 3));
+        assert_eq!(increase(&["increase", "5", "--by=3"]), (5, 3));
+        assert_eq!(increase(&["increase", "-b3", "5"]), (5, 3));
+        assert_eq!(increase(&["increase", "5", "-b=3"]), (5, 3));
+    }
+
+    #[test]
+    fn negative_numbers_are_positionals() {
+        assert_eq!(increase(&["increase", "-5", "-b", "-2"]), (-5, -2));
+    }
+
+    #[test]
+    fn global_flags_after_the_subcommand() {
+        let matches = parse(&["increase", "5", "-v"]).unwrap();
+        assert!(matches.is_present("verbose"));
+        assert!(matches.subcommand().unwrap().1.is_present("verbose"));
+        // `--quiet` isn't global
+        assert!(matches!(
+            parse(&["increase", "5", "--quiet"]),
+            Err(Error::UnknownFlag { .. })
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse(&["--verbos"]).unwrap_err(),
+            Error::UnknownFlag {
+                flag: "--verbos".to_string(),
+                suggestion: Some("--verbose".to_string()),
+            }
+        );
+        assert_eq!(
+            parse(&["-x"]).unwrap_err(),
+            Error::UnknownFlag {
+                flag: "-x".to_string(),
+                suggestion: None,
+            }
+        );
+        assert_eq!(
+            parse(&["increse", "5"]).unwrap_err(),
+            Error::UnknownCommand {
+                name: "increse".to_string(),
+                suggestion: Some("increase".to_string()),
+            }
+        );
+        assert_eq!(
+            parse(&["increase"]).unwrap_err(),
+            Error::MissingArgument("integer".to_string())
+        );
+        assert_eq!(
+            parse(&["increase", "5", "--by"]).unwrap_err(),
+            Error::MissingValue("--by".to_string())
+        );
+        assert_eq!(
+            parse(&["--verbose=yes"]).unwrap_err(),
+            Error::UnexpectedValue("--verbose".to_string())
+        );
+        assert_eq!(
+            parse(&["a", "b"]).unwrap_err(),
+            Error::UnexpectedArgument("b".to_string())
+        );
+        assert!(matches!(parse(&["--help"]), Err(Error::Help(_))));
+    }
+
+    #[test]
+    fn invalid_values() {
+        let matches = parse(&["increase", "five"]).unwrap();
+        let (_, sub) = matches.subcommand().unwrap();
+        assert!(matches!(
+            sub.value::<i32>("integer"),
+            Err(Error::InvalidValue { name, value, .. }) if name == "integer" && value == "five"
+        ));
+    }
+}
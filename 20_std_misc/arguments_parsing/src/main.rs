@@ -1,63 +1,107 @@
+mod cli;
+
+use cli::{Arg, Command, Error, Matches};
 use std::env;
+use std::process;
 
-fn increase(number: i32) {
-    println!("{}", number + 1);
+fn increase(number: i32, by: i32) -> Result<(), Error> {
+    let result = number
+        .checked_add(by)
+        .ok_or_else(|| out_of_range(number, "increasing", by))?;
+    println!("{}", result);
+    Ok(())
 }
 
-fn decrease(number: i32) {
-    println!("{}", number - 1);
+fn decrease(number: i32, by: i32) -> Result<(), Error> {
+    let result = number
+        .checked_sub(by)
+        .ok_or_else(|| out_of_range(number, "decreasing", by))?;
+    println!("{}", result);
+    Ok(())
 }
 
-fn help() {
-    println!(
-        "usage:
-match_args <string>
-    Check whether given string is the answer.
-match_args {{increase|decrease}} <integer>
-    Increase or decrease given integer by one."
-    );
+fn out_of_range(number: i32, action: &str, by: i32) -> Error {
+    Error::InvalidValue {
+        name: "integer".to_string(),
+        value: number.to_string(),
+        reason: format!("{} it by {} overflows an i32", action, by),
+    }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+fn command() -> Command {
+    let by = Arg::option("by")
+        .short('b')
+        .default("1")
+        .help("Amount to change the integer by");
 
-    match args.len() {
-        // no arguments passed
-        1 => {
-            println!("My name is 'match_args'. Try passing some arguments!");
-        }
-        // one argument passed
-        2 => match args[1].parse() {
-            Ok(42) => println!("This is the answer!"),
-            _ => println!("This is not the answer."),
-        },
-        // one command and one argument passed
-        3 => {
-            let cmd = &args[1];
-            let num = &args[2];
-            // parse the number
-            let number: i32 = match num.parse() {
-                Ok(n) => n,
-                Err(_) => {
-                    eprintln!("error: second argument not an integer");
-                    help();
-                    return;
-                }
-            };
-            // parse the command
-            match &cmd[..] {
-                "increase" => increase(number),
-                "decrease" => decrease(number),
-                _ => {
-                    eprintln!("error: invalid command");
-                    help();
-                }
+    Command::new("match_args")
+        .about("Check numbers against the answer, or increase and decrease them.")
+        .arg(
+            Arg::positional("string")
+                .optional()
+                .help("Check whether given string is the answer"),
+        )
+        .arg(
+            Arg::flag("verbose")
+                .short('v')
+                .global()
+                .help("Explain what is being done"),
+        )
+        .subcommand(
+            Command::new("increase")
+                .about("Increase given integer, by one unless --by says otherwise")
+                .arg(Arg::positional("integer").help("The integer to change"))
+                .arg(by.clone()),
+        )
+        .subcommand(
+            Command::new("decrease")
+                .about("Decrease given integer, by one unless --by says otherwise")
+                .arg(Arg::positional("integer").help("The integer to change"))
+                .arg(by),
+        )
+}
+
+fn run(matches: &Matches) -> Result<(), Error> {
+    let verbose = matches.is_present("verbose");
+
+    match matches.subcommand() {
+        Some((cmd, sub)) => {
+            // Values are parsed into the type the caller asks for
+            let number: i32 = sub.value("integer")?;
+            let by: i32 = sub.value("by")?;
+            if verbose {
+                println!("{} {} by {}", cmd, number, by);
+            }
+            match cmd {
+                "increase" => increase(number, by)?,
+                "decrease" => decrease(number, by)?,
+                _ => unreachable!(),
             }
         }
-        // all the other cases
-        _ => {
-            // show a help message
-            help();
+        None => match matches.try_value::<String>("string")? {
+            Some(string) => match string.parse() {
+                Ok(42) => println!("This is the answer!"),
+                _ => println!("This is not the answer."),
+            },
+            // no arguments passed
+            None => println!("My name is 'match_args'. Try passing some arguments!"),
+        },
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let result = command().parse(env::args()).and_then(|m| run(&m));
+
+    match result {
+        Ok(()) => {}
+        // `--help` is not a failure, the text goes to stdout
+        Err(Error::Help(help)) => print!("{}", help),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            eprintln!("For more information, try '--help'.");
+            process::exit(2);
         }
     }
 }
@@ -1,3 +1,6 @@
+mod pool;
+
+use pool::{SubmitError, ThreadPool};
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
 use std::thread;
@@ -44,4 +47,36 @@ fn main() {
 
     // Show the order in which the messages were sent
     println!("{:?}", ids);
+
+    // A pool of `NTHREADS` workers sharing a queue of at most 2 pending jobs.
+    // Each job hands back a typed result through its own handle.
+    let pool = ThreadPool::new(NTHREADS as usize, 2);
+    let handles: Vec<_> = (0..10)
+        .map(|n| pool.submit(move || n * n).unwrap())
+        .collect();
+    let squares: Vec<i32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    println!("squares: {:?}", squares);
+
+    // A panicking job is reported through its handle, and the worker that
+    // ran it keeps serving the queue
+    let bad = pool.submit(|| -> i32 { panic!("bad input") }).unwrap();
+    println!("panicking job: {:?}", bad.join());
+    println!("after the panic: {:?}", pool.submit(|| 6 * 7).unwrap().join());
+    pool.shutdown();
+
+    // With one busy worker and a queue of one, the next `try_submit` is
+    // rejected instead of blocking
+    let pool = ThreadPool::new(1, 1);
+    let (gate_tx, gate_rx) = mpsc::channel::<()>();
+    let busy = pool.submit(move || gate_rx.recv().is_ok()).unwrap();
+    // Give the worker time to pick up the first job
+    thread::sleep(std::time::Duration::from_millis(50));
+    let queued = pool.submit(|| "queued").unwrap();
+    assert_eq!(pool.try_submit(|| "rejected").err(), Some(SubmitError::Full));
+    println!("queue full, try_submit was rejected");
+    gate_tx.send(()).unwrap();
+
+    // Shutdown drains the queue, so the queued job still runs
+    pool.shutdown();
+    println!("busy: {:?}, queued: {:?}", busy.join(), queued.join());
 }
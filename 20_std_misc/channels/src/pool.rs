@@ -0,0 +1,181 @@
+// A fixed-size worker pool fed through a bounded `sync_channel`.
+//
+// The job queue holds at most `capacity` pending jobs. Once it is full,
+// `submit` blocks until a worker frees a slot, while `try_submit` returns
+// `SubmitError::Full` immediately. That is the backpressure: producers can
+// never run arbitrarily far ahead of the workers.
+
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmitError {
+    // The queue is at capacity (only returned by `try_submit`)
+    Full,
+    // The pool is shutting down and accepts no more jobs
+    Closed,
+}
+
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubmitError::Full => write!(f, "job queue is full"),
+            SubmitError::Closed => write!(f, "pool is shut down"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobError {
+    // The job panicked, carries the panic message
+    Panicked(String),
+    // The job was dropped without running
+    Lost,
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JobError::Panicked(msg) => write!(f, "job panicked: {}", msg),
+            JobError::Lost => write!(f, "job was dropped before it ran"),
+        }
+    }
+}
+
+// The receiving side of a submitted job
+pub struct JobHandle<T> {
+    result: Receiver<Result<T, JobError>>,
+}
+
+impl<T> JobHandle<T> {
+    // Blocks until the job has finished and returns its result
+    pub fn join(self) -> Result<T, JobError> {
+        self.result.recv().unwrap_or(Err(JobError::Lost))
+    }
+}
+
+pub struct ThreadPool {
+    // `None` once shutdown has started
+    sender: Option<SyncSender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ThreadPool {
+    pub fn new(size: usize, capacity: usize) -> ThreadPool {
+        assert!(size > 0, "a pool needs at least one worker");
+
+        let (sender, receiver) = mpsc::sync_channel::<Job>(capacity);
+        // Workers take turns pulling from the single receiver
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let workers = (0..size)
+            .map(|id| {
+                let receiver = Arc::clone(&receiver);
+                thread::Builder::new()
+                    .name(format!("worker-{}", id))
+                    .spawn(move || loop {
+                        // The guard is a temporary, so the lock is released
+                        // before the job runs
+                        let job = receiver.lock().unwrap().recv();
+                        match job {
+                            Ok(job) => job(),
+                            // Every sender is gone and the queue is drained
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("failed to spawn worker thread")
+            })
+            .collect();
+
+        ThreadPool {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    // Queues `f`, blocking while the queue is full
+    pub fn submit<F, T>(&self, f: F) -> Result<JobHandle<T>, SubmitError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (job, handle) = wrap(f);
+        let sender = self.sender.as_ref().ok_or(SubmitError::Closed)?;
+        sender.send(job).map_err(|_| SubmitError::Closed)?;
+        Ok(handle)
+    }
+
+    // Queues `f` only if there is room right now
+    pub fn try_submit<F, T>(&self, f: F) -> Result<JobHandle<T>, SubmitError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (job, handle) = wrap(f);
+        let sender = self.sender.as_ref().ok_or(SubmitError::Closed)?;
+        sender.try_send(job).map_err(|e| match e {
+            TrySendError::Full(_) => SubmitError::Full,
+            TrySendError::Disconnected(_) => SubmitError::Closed,
+        })?;
+        Ok(handle)
+    }
+
+    // Stops accepting jobs, lets the workers drain the queue and waits
+    // for all of them to exit
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        // Dropping the sender disconnects the channel, `recv` keeps
+        // returning queued jobs and only fails once the queue is empty
+        drop(self.sender.take());
+
+        for worker in self.workers.drain(..) {
+            // Jobs run under `catch_unwind`, so a worker can only panic
+            // if the pool itself is broken
+            worker.join().expect("worker thread panicked");
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// Turns `f` into a type-erased job that reports its result, or its panic,
+// through a one-shot channel
+fn wrap<F, T>(f: F) -> (Job, JobHandle<T>)
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (tx, rx) = mpsc::sync_channel(1);
+
+    let job = Box::new(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(f))
+            .map_err(|payload| JobError::Panicked(panic_message(payload)));
+        // The caller may have dropped the handle, that's fine
+        let _ = tx.send(result);
+    });
+
+    (job, JobHandle { result: rx })
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
@@ -1,4 +1,8 @@
+mod runner;
+
+use runner::Runner;
 use std::process::Command;
+use std::time::Duration;
 
 fn main() {
     let output = Command::new("rustc")
@@ -15,4 +19,74 @@ fn main() {
 
         print!("rustc failed and stderr was:\n{}", s);
     }
+
+    // The same, through `Runner`, which also reports how long it took
+    let outcome = Runner::new("rustc")
+        .arg("--version")
+        .run()
+        .unwrap_or_else(|e| panic!("failed to execute process: {}", e));
+    if outcome.success() {
+        print!(
+            "rustc exited after {:?} and stdout was:\n{}",
+            outcome.duration,
+            outcome.stdout_lossy()
+        );
+    } else {
+        print!(
+            "rustc exited with {:?} and stderr was:\n{}",
+            outcome.code,
+            outcome.stderr_lossy()
+        );
+    }
+
+    // A child that outlives its timeout is killed and reaped
+    let outcome = Runner::new("sleep")
+        .arg("5")
+        .timeout(Duration::from_millis(200))
+        .run()
+        .unwrap();
+    println!(
+        "sleep timed out: {}, signal: {:?}, after {:?}",
+        outcome.timed_out, outcome.signal, outcome.duration
+    );
+
+    // Both pipes are drained concurrently, so a child writing far more than
+    // a pipe buffer to each of them does not deadlock
+    let outcome = Runner::new("sh")
+        .args([
+            "-c",
+            "head -c 1000000 /dev/zero; head -c 1000000 /dev/zero >&2",
+        ])
+        .timeout(Duration::from_secs(10))
+        .run()
+        .unwrap();
+    println!(
+        "captured {} bytes of stdout and {} bytes of stderr",
+        outcome.stdout.len(),
+        outcome.stderr.len()
+    );
+
+    // Environment, working directory and stdin can be overridden
+    let outcome = Runner::new("sh")
+        .args(["-c", "echo \"$GREETING from $(pwd)\"; cat; exit 3"])
+        .env("GREETING", "hello")
+        .env_remove("HOME")
+        .current_dir("/")
+        .stdin("and hello from stdin\n")
+        .run()
+        .unwrap();
+    print!(
+        "sh exited with {:?} (success: {}):\n{}",
+        outcome.code,
+        outcome.success(),
+        outcome.stdout_lossy()
+    );
+
+    // Or the child can start from an empty environment
+    let outcome = Runner::new("/usr/bin/env")
+        .env_clear()
+        .env("ONLY", "this")
+        .run()
+        .unwrap();
+    print!("cleared environment:\n{}", outcome.stdout_lossy());
 }
@@ -0,0 +1,370 @@
+// A process runner built on top of `process::Command`.
+//
+// `Command::output()` waits for as long as the child likes. `Runner` adds a
+// wall-clock timeout after which the child is killed and reaped, and drains
+// stdout and stderr on their own threads: a child that fills one pipe while
+// we block reading the other would otherwise deadlock.
+//
+// On Unix the child runs in a process group of its own, and a timeout
+// kills the whole group. Otherwise a grandchild, like whatever `sh -c`
+// started, would keep stdout or stderr open and `run` would wait for it
+// past the deadline. Being in its own group also means a Ctrl-C in the
+// terminal doesn't reach the child.
+//
+// The deadline also bounds reading the output: a background grandchild
+// can hold the pipes open after the child itself has exited, and then
+// it's the group that is killed once time is up.
+
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often a child with a timeout is polled for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// How long the output is still read after killing the process group. Only
+// a process outside the group can keep the pipes open for longer, and what
+// it holds is given up on.
+const DRAIN_GRACE: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub struct Runner {
+    program: OsString,
+    args: Vec<OsString>,
+    // `None` removes the variable from the child's environment
+    envs: Vec<(OsString, Option<OsString>)>,
+    env_clear: bool,
+    current_dir: Option<PathBuf>,
+    stdin: Option<Vec<u8>>,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    // Exit code, `None` if the child was terminated by a signal
+    pub code: Option<i32>,
+    // Terminating signal, always `None` outside of Unix
+    pub signal: Option<i32>,
+    // Whether the child, or something it started, was killed because it
+    // exceeded the timeout
+    pub timed_out: bool,
+    pub duration: Duration,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl Outcome {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    pub fn stdout_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.stdout).into_owned()
+    }
+
+    pub fn stderr_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.stderr).into_owned()
+    }
+}
+
+impl Runner {
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Runner {
+        Runner {
+            program: program.as_ref().to_os_string(),
+            args: Vec::new(),
+            envs: Vec::new(),
+            env_clear: false,
+            current_dir: None,
+            stdin: None,
+            timeout: None,
+        }
+    }
+
+    pub fn arg<S: AsRef<OsStr>>(mut self, arg: S) -> Runner {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Runner
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args
+            .extend(args.into_iter().map(|a| a.as_ref().to_os_string()));
+        self
+    }
+
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(mut self, key: K, value: V) -> Runner {
+        self.envs.push((
+            key.as_ref().to_os_string(),
+            Some(value.as_ref().to_os_string()),
+        ));
+        self
+    }
+
+    pub fn env_remove<K: AsRef<OsStr>>(mut self, key: K) -> Runner {
+        self.envs.push((key.as_ref().to_os_string(), None));
+        self
+    }
+
+    // Starts the child with an empty environment, apart from the
+    // variables set with `env`
+    pub fn env_clear(mut self) -> Runner {
+        self.env_clear = true;
+        self.envs.clear();
+        self
+    }
+
+    pub fn current_dir<P: AsRef<Path>>(mut self, dir: P) -> Runner {
+        self.current_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    // Bytes written to the child's stdin, which is closed afterwards.
+    // Without this the child gets no stdin at all.
+    pub fn stdin<B: Into<Vec<u8>>>(mut self, input: B) -> Runner {
+        self.stdin = Some(input.into());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Runner {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn run(&self) -> io::Result<Outcome> {
+        let mut command = self.command();
+        let start = Instant::now();
+        let mut child = command.spawn()?;
+
+        // Start draining before anything can block on a full pipe
+        let stdout = drain(child.stdout.take());
+        let stderr = drain(child.stderr.take());
+        let stdin = self.stdin.clone().and_then(|input| {
+            let mut pipe = child.stdin.take()?;
+            // A child that exits without reading gives us a broken pipe,
+            // which is not an error from the caller's point of view
+            Some(thread::spawn(move || {
+                let _ = pipe.write_all(&input);
+            }))
+        });
+
+        let deadline = self.timeout.map(|timeout| start + timeout);
+        let (status, mut timed_out) = match deadline {
+            Some(deadline) => wait_deadline(&mut child, deadline)?,
+            None => (child.wait()?, false),
+        };
+        let duration = start.elapsed();
+
+        let mut stdout_read = collect(&stdout, deadline)?;
+        let mut stderr_read = collect(&stderr, deadline)?;
+        if stdout_read.is_none() || stderr_read.is_none() {
+            // Something the child started still has a pipe open
+            kill_group(&mut child);
+            timed_out = true;
+            let grace = Some(Instant::now() + DRAIN_GRACE);
+            if stdout_read.is_none() {
+                stdout_read = collect(&stdout, grace)?;
+            }
+            if stderr_read.is_none() {
+                stderr_read = collect(&stderr, grace)?;
+            }
+        }
+
+        if let Some(stdin) = stdin {
+            let _ = stdin.join();
+        }
+        let stdout = stdout_read.unwrap_or_default();
+        let stderr = stderr_read.unwrap_or_default();
+
+        Ok(Outcome {
+            code: status.code(),
+            signal: signal(&status),
+            timed_out,
+            duration,
+            stdout,
+            stderr,
+        })
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+
+        if self.env_clear {
+            command.env_clear();
+        }
+        for (key, value) in &self.envs {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+
+        command
+            .stdin(if self.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        command
+    }
+}
+
+// Polls the child until it exits or the deadline passes. A child that is
+// still running at the deadline is killed and then reaped, so it never
+// lingers as a zombie.
+fn wait_deadline(child: &mut Child, deadline: Instant) -> io::Result<(ExitStatus, bool)> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, false));
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            // The child may exit between `try_wait` and `kill`, in which
+            // case `kill` fails harmlessly and `wait` reaps it
+            kill_group(child);
+            return Ok((child.wait()?, true));
+        }
+
+        thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+// Kills the child and everything in its process group, whose id is the
+// child's pid. That works after the child has exited too, as long as
+// something in the group still runs.
+#[cfg(unix)]
+fn kill_group(child: &mut Child) {
+    // SAFETY: `kill` has no memory-safety preconditions. A pid isn't reused
+    // while the child is unreaped or while its group has members.
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+// Reads the pipe to the end on its own thread, which sends the result
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> Option<Receiver<io::Result<Vec<u8>>>> {
+    pipe.map(|mut pipe| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = sender.send(pipe.read_to_end(&mut buf).map(|_| buf));
+        });
+        receiver
+    })
+}
+
+// Waits for a reader until the deadline, if there is one. `Ok(None)` if
+// it's still reading by then, so it can be waited for again.
+fn collect(
+    reader: &Option<Receiver<io::Result<Vec<u8>>>>,
+    deadline: Option<Instant>,
+) -> io::Result<Option<Vec<u8>>> {
+    let Some(reader) = reader else {
+        return Ok(Some(Vec::new()));
+    };
+    let received = match deadline {
+        Some(deadline) => reader.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+        None => reader.recv().map_err(RecvTimeoutError::from),
+    };
+    match received {
+        Ok(output) => output.map(Some),
+        Err(RecvTimeoutError::Timeout) => Ok(None),
+        Err(RecvTimeoutError::Disconnected) => Err(io::Error::other("output reader panicked")),
+    }
+}
+
+#[cfg(unix)]
+fn signal(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_kills_grandchildren_holding_the_pipes() {
+        // `Outcome::duration` stops at the child's exit, before the pipes
+        // are drained, so time the whole call instead
+        let start = Instant::now();
+        let outcome = Runner::new("sh")
+            .args(["-c", "sleep 3; echo done"])
+            .timeout(Duration::from_millis(200))
+            .run()
+            .unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(outcome.timed_out);
+        assert!(
+            elapsed < Duration::from_secs(1),
+            "returned after {:?}",
+            elapsed
+        );
+        assert_eq!(outcome.stdout_lossy(), "");
+    }
+
+    #[test]
+    fn timeout_covers_background_grandchildren() {
+        // The child exits at once, but the `sleep` it left behind keeps
+        // stdout open
+        let start = Instant::now();
+        let outcome = Runner::new("sh")
+            .args(["-c", "sleep 3 & echo hi"])
+            .timeout(Duration::from_millis(200))
+            .run()
+            .unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(outcome.timed_out);
+        assert!(
+            elapsed < Duration::from_secs(1),
+            "returned after {:?}",
+            elapsed
+        );
+        assert_eq!(outcome.stdout_lossy(), "hi\n");
+    }
+
+    #[test]
+    fn fast_child_is_not_timed_out() {
+        let outcome = Runner::new("sh")
+            .args(["-c", "echo hi"])
+            .timeout(Duration::from_secs(5))
+            .run()
+            .unwrap();
+
+        assert!(!outcome.timed_out);
+        assert!(outcome.success());
+        assert_eq!(outcome.stdout_lossy(), "hi\n");
+    }
+}
@@ -0,0 +1,220 @@
+// Atomic and durable file writes.
+//
+// `File::create` truncates the target first, so a crash halfway through
+// the write leaves a half-written file behind. Here the new contents go to
+// a temporary file in the same directory, which is flushed to disk and
+// then renamed over the target. A rename within one filesystem is atomic:
+// readers see either the old file or the new one, never a mix. Finally
+// the directory itself is synced so that the rename survives a crash too.
+//
+// If the target is a symlink, the file it points to is replaced and the
+// link is kept, as with `File::create`. Renaming over the link itself would
+// turn it into a regular file.
+
+use std::error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, prelude::*};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    // Replace the contents of the target, creating it if needed
+    Replace,
+    // Add to the end of the existing contents, creating the target if needed
+    Append,
+    // Fail if the target already exists
+    CreateNew,
+}
+
+#[derive(Debug)]
+pub struct Error {
+    path: PathBuf,
+    action: &'static str,
+    source: io::Error,
+}
+
+impl Error {
+    pub fn kind(&self) -> io::ErrorKind {
+        self.source.kind()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "couldn't {} {}: {}",
+            self.action,
+            self.path.display(),
+            self.source
+        )
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+// Attaches the path and what we were doing with it to an `io::Error`
+trait Context<T> {
+    fn context(self, action: &'static str, path: &Path) -> Result<T, Error>;
+}
+
+impl<T> Context<T> for io::Result<T> {
+    fn context(self, action: &'static str, path: &Path) -> Result<T, Error> {
+        self.map_err(|source| Error {
+            path: path.to_path_buf(),
+            action,
+            source,
+        })
+    }
+}
+
+// Removes the temporary file unless it has been renamed into place
+struct TempFile {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+pub fn write<P: AsRef<Path>>(path: P, contents: &[u8]) -> Result<(), Error> {
+    write_with(path.as_ref(), contents, Mode::Replace)
+}
+
+pub fn append<P: AsRef<Path>>(path: P, contents: &[u8]) -> Result<(), Error> {
+    write_with(path.as_ref(), contents, Mode::Append)
+}
+
+pub fn create_new<P: AsRef<Path>>(path: P, contents: &[u8]) -> Result<(), Error> {
+    write_with(path.as_ref(), contents, Mode::CreateNew)
+}
+
+pub fn write_with(path: &Path, contents: &[u8], mode: Mode) -> Result<(), Error> {
+    let path = &resolve(path)?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let existing = match fs::metadata(path) {
+        Ok(metadata) => Some(metadata),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e).context("inspect", path),
+    };
+    if mode == Mode::CreateNew && existing.is_some() {
+        return Err(io::Error::from(io::ErrorKind::AlreadyExists)).context("create", path);
+    }
+
+    let (mut file, mut temp) = create_temp(dir, path)?;
+
+    // Appending copies the old contents into the temporary file first, so
+    // the target is still only ever swapped as a whole
+    if mode == Mode::Append && existing.is_some() {
+        let mut original = File::open(path).context("open", path)?;
+        io::copy(&mut original, &mut file).context("copy", path)?;
+    }
+
+    file.write_all(contents).context("write", &temp.path)?;
+    if let Some(metadata) = &existing {
+        fs::set_permissions(&temp.path, metadata.permissions())
+            .context("set permissions on", &temp.path)?;
+    }
+    file.sync_all().context("sync", &temp.path)?;
+    drop(file);
+
+    if mode == Mode::CreateNew {
+        // A hard link fails if the target exists, unlike a rename, so a
+        // file created in the meantime is never overwritten
+        fs::hard_link(&temp.path, path).context("create", path)?;
+    } else {
+        fs::rename(&temp.path, path).context("replace", path)?;
+        temp.keep = true;
+    }
+
+    sync_dir(dir)
+}
+
+// Follows `path` while it's a symlink. Unlike `fs::canonicalize` this works
+// for a link to a file that doesn't exist yet, which writing then creates.
+fn resolve(path: &Path) -> Result<PathBuf, Error> {
+    let mut path = path.to_path_buf();
+    // Linux gives up after as many links
+    for _ in 0..40 {
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let target = fs::read_link(&path).context("resolve", &path)?;
+                // A relative target is relative to the link's directory
+                path = match path.parent() {
+                    Some(dir) => dir.join(target),
+                    None => target,
+                };
+            }
+            // Anything else is for `write_with` to deal with
+            _ => return Ok(path),
+        }
+    }
+    Err(io::Error::other("too many levels of symbolic links")).context("resolve", &path)
+}
+
+fn create_temp(dir: &Path, path: &Path) -> Result<(File, TempFile), Error> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file name"))
+        .context("write", path)?;
+
+    loop {
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(name);
+        temp_name.push(format!(
+            ".{}.{}.tmp",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = dir.join(temp_name);
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => {
+                let temp = TempFile {
+                    path: temp_path,
+                    keep: false,
+                };
+                return Ok((file, temp));
+            }
+            // Left over from a crashed run, try the next name
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e).context("create a temporary file for", path),
+        }
+    }
+}
+
+// Makes a rename in `dir` durable. Only Unix lets us open and sync a
+// directory.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<(), Error> {
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .context("sync", dir)
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<(), Error> {
+    Ok(())
+}
@@ -7,23 +7,36 @@ cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non
 proident, sunt in culpa qui officia deserunt mollit anim id est laborum.
 ";
 
-use std::fs::File;
-use std::io::prelude::*;
+mod atomic;
+
 use std::path::Path;
 
 fn main() {
     let path = Path::new("lorem_ipsum.txt");
     let display = path.display();
 
-    // Open a file in write-only mode, returns `io::Result<File>`
-    let mut file = match File::create(&path) {
-        Err(why) => panic!("couldn't create {}: {}", display, why),
-        Ok(file) => file,
-    };
-
-    // Write the `LOREM_IPSUM` string to `file`, returns `io::Result<()>`
-    match file.write_all(LOREM_IPSUM.as_bytes()) {
-        Err(why) => panic!("couldn't write to {}: {}", display, why),
+    // Write the `LOREM_IPSUM` string to a temporary file next to `path`
+    // and rename it over `path` once it is safely on disk, so `path` is
+    // never left half-written. Returns `Result<(), atomic::Error>`.
+    match atomic::write(path, LOREM_IPSUM.as_bytes()) {
+        Err(why) => eprintln!("{}", why),
         Ok(_) => println!("successfully wrote to {}", display),
     }
+
+    // Appending goes through a temporary copy as well
+    match atomic::append(path, b"\n-- the end\n") {
+        Err(why) => eprintln!("{}", why),
+        Ok(_) => println!("successfully appended to {}", display),
+    }
+
+    // `create_new` refuses to touch an existing file, the error names it
+    match atomic::create_new(path, LOREM_IPSUM.as_bytes()) {
+        Err(why) => eprintln!("{} ({:?})", why, why.kind()),
+        Ok(_) => println!("successfully created {}", display),
+    }
+
+    // Errors carry the path instead of panicking
+    if let Err(why) = atomic::write("no/such/dir/lorem_ipsum.txt", LOREM_IPSUM.as_bytes()) {
+        eprintln!("{}", why);
+    }
 }
@@ -1,6 +1,12 @@
-use std::fs::File;
+mod reader;
+
+use reader::{Follower, Utf8Mode};
+use std::env;
+use std::fs::{self, File, OpenOptions};
 use std::io::prelude::*;
 use std::path::Path;
+use std::thread;
+use std::time::Duration;
 
 fn main() {
     // Create a path to the desired file
@@ -8,15 +14,87 @@ fn main() {
     let display = path.display();
 
     // Open the path in read-only mode, returns `io::Result<File>`
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => panic!("couldn't open {}: {}", display, why),
         Ok(file) => file,
     };
 
-    // Read the file contents into a string, returns `io::Result<usize>`
-    let mut s = String::new();
-    match file.read_to_string(&mut s) {
+    // Read the file contents into bytes, returns `io::Result<usize>`, and
+    // decode them strictly. Invalid UTF-8 is reported with its offset
+    // instead of panicking.
+    let mut bytes = Vec::new();
+    match file.read_to_end(&mut bytes) {
         Err(why) => panic!("couldn't read {}: {}", display, why),
-        Ok(_) => println!("{} contains: \n{}", display, s),
+        Ok(_) => match reader::decode(&bytes, Utf8Mode::Strict) {
+            Err(why) => println!("{} is not text: {}", display, why),
+            Ok(s) => println!("{} contains: \n{}", display, s),
+        },
+    }
+    // `file` goes out of scope, and the "hello.txt" file gets closed
+    drop(file);
+
+    // The other modes are shown on a larger file of our own
+    let log = env::temp_dir().join(format!("open-example-{}.log", std::process::id()));
+    let mut contents = Vec::new();
+    for i in 0..100_000 {
+        writeln!(contents, "line {}", i).unwrap();
+    }
+    fs::write(&log, &contents).unwrap();
+
+    // Stream it in 256 KiB chunks, reporting progress as we go
+    let mut newlines = 0;
+    let read = reader::read_chunked(
+        &log,
+        256 * 1024,
+        |chunk| {
+            newlines += chunk.iter().filter(|&&b| b == b'\n').count();
+            Ok(())
+        },
+        |progress| {
+            println!(
+                "read {:>7} bytes ({:.0}%)",
+                progress.bytes_read,
+                progress.percent()
+            )
+        },
+    )
+    .unwrap();
+    println!("{} bytes, {} lines", read, newlines);
+
+    // Or map it and let the OS page it in
+    let map = reader::map(&log).unwrap();
+    let last = map[..map.len() - 1].rsplit(|&b| b == b'\n').next().unwrap();
+    println!(
+        "last line through mmap: {:?}",
+        reader::decode(last, Utf8Mode::Strict).unwrap()
+    );
+
+    // Bad bytes are located in strict mode and replaced in lossy mode
+    let bad = b"caf\xc3\xa9 \xff ok";
+    println!("strict: {:?}", reader::decode(bad, Utf8Mode::Strict));
+    println!("lossy: {:?}", reader::decode(bad, Utf8Mode::Lossy));
+
+    // Follow the file while another thread appends to it
+    let mut follower = Follower::open(&log)
+        .unwrap()
+        .poll_interval(Duration::from_millis(10));
+    let writer = {
+        let log = log.clone();
+        thread::spawn(move || {
+            let mut file = OpenOptions::new().append(true).open(log).unwrap();
+            for line in ["appended 1\n", "appended ", "2\n"] {
+                thread::sleep(Duration::from_millis(50));
+                file.write_all(line.as_bytes()).unwrap();
+            }
+        })
+    };
+    while let Some(line) = follower
+        .next_line(Some(Duration::from_millis(500)))
+        .unwrap()
+    {
+        println!("followed: {}", line);
     }
-} // `file` goes out of scope, and the "hello.txt" file gets closed
+    writer.join().unwrap();
+
+    fs::remove_file(&log).unwrap();
+}
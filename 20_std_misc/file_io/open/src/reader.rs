@@ -0,0 +1,176 @@
+// Ways of reading files too big to load into a `String` in one go.
+//
+// * `read_chunked` streams a file through a fixed-size buffer and reports
+//   progress after every chunk.
+// * `map` memory-maps a file read-only, letting the OS page it in on
+//   demand.
+// * `decode` turns bytes into text, either strictly, failing with the
+//   offset of the first invalid sequence, or lossily.
+// * `Follower` behaves like `tail -f`, waiting for lines to be appended.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, prelude::*, BufReader, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use memmap2::Mmap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub bytes_read: u64,
+    // Size of the file when reading started
+    pub total: u64,
+}
+
+impl Progress {
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.bytes_read as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+// Calls `on_chunk` with successive chunks of at most `chunk_size` bytes and
+// `on_progress` after each of them. Returns the number of bytes read.
+pub fn read_chunked<P, F, G>(
+    path: P,
+    chunk_size: usize,
+    mut on_chunk: F,
+    mut on_progress: G,
+) -> io::Result<u64>
+where
+    P: AsRef<Path>,
+    F: FnMut(&[u8]) -> io::Result<()>,
+    G: FnMut(Progress),
+{
+    let mut file = File::open(path)?;
+    let total = file.metadata()?.len();
+    let mut buf = vec![0; chunk_size.max(1)];
+    let mut bytes_read = 0;
+
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(0) => return Ok(bytes_read),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        on_chunk(&buf[..n])?;
+        bytes_read += n as u64;
+        on_progress(Progress { bytes_read, total });
+    }
+}
+
+// Maps the whole file into memory, read-only. The `Mmap` derefs to `[u8]`.
+pub fn map<P: AsRef<Path>>(path: P) -> io::Result<Mmap> {
+    let file = File::open(path)?;
+    // SAFETY: the mapping is only read through. If another process
+    // truncates the file while it is mapped, accessing the missing pages
+    // raises SIGBUS, which is the usual caveat of mmap and not something
+    // the type system can prevent.
+    unsafe { Mmap::map(&file) }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Utf8Mode {
+    // Fail on the first invalid sequence
+    Strict,
+    // Replace invalid sequences with U+FFFD
+    Lossy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Utf8Error {
+    // Byte offset of the first invalid sequence
+    pub offset: usize,
+}
+
+impl fmt::Display for Utf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid UTF-8 at byte offset {}", self.offset)
+    }
+}
+
+impl std::error::Error for Utf8Error {}
+
+// Borrows when the bytes are valid UTF-8, which is the common case
+pub fn decode(bytes: &[u8], mode: Utf8Mode) -> Result<Cow<'_, str>, Utf8Error> {
+    match mode {
+        Utf8Mode::Lossy => Ok(String::from_utf8_lossy(bytes)),
+        Utf8Mode::Strict => std::str::from_utf8(bytes)
+            .map(Cow::Borrowed)
+            .map_err(|e| Utf8Error {
+                offset: e.valid_up_to(),
+            }),
+    }
+}
+
+// Follows a growing file, like `tail -f`. Lines are decoded lossily, since
+// a log with one bad byte should not stop the follower.
+pub struct Follower {
+    path: PathBuf,
+    reader: BufReader<File>,
+    pos: u64,
+    poll: Duration,
+    // A line whose newline hasn't been written yet
+    partial: Vec<u8>,
+}
+
+impl Follower {
+    // Starts at the current end of the file, so only new lines are seen
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Follower> {
+        let mut file = File::open(&path)?;
+        let pos = file.seek(SeekFrom::End(0))?;
+
+        Ok(Follower {
+            path: path.as_ref().to_path_buf(),
+            reader: BufReader::new(file),
+            pos,
+            poll: Duration::from_millis(100),
+            partial: Vec::new(),
+        })
+    }
+
+    pub fn poll_interval(mut self, poll: Duration) -> Follower {
+        self.poll = poll;
+        self
+    }
+
+    // Waits for the next complete line, without its line ending. Gives up
+    // and returns `None` after `timeout`, or never if it is `None`.
+    pub fn next_line(&mut self, timeout: Option<Duration>) -> io::Result<Option<String>> {
+        let deadline = timeout.map(|t| Instant::now() + t);
+
+        loop {
+            let n = self.reader.read_until(b'\n', &mut self.partial)?;
+            self.pos += n as u64;
+
+            if self.partial.ends_with(b"\n") {
+                let line = String::from_utf8_lossy(&self.partial)
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string();
+                self.partial.clear();
+                return Ok(Some(line));
+            }
+
+            if n == 0 {
+                // A file shorter than what we've read was truncated, as
+                // log rotation with `copytruncate` does. Start over.
+                if std::fs::metadata(&self.path)?.len() < self.pos {
+                    self.pos = self.reader.seek(SeekFrom::Start(0))?;
+                    self.partial.clear();
+                    continue;
+                }
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    return Ok(None);
+                }
+                thread::sleep(self.poll);
+            }
+        }
+    }
+}
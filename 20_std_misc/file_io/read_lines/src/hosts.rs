@@ -0,0 +1,249 @@
+// A parser for the `/etc/hosts` format:
+//
+//     # comment
+//     127.0.0.1   localhost
+//     ::1         localhost ip6-localhost ip6-loopback
+//     10.0.0.5    db.internal db    # trailing comments are allowed
+//
+// Every non-blank line holds an address followed by a canonical hostname
+// and any number of aliases. Malformed lines don't stop parsing: they're
+// collected, with their line number, alongside the valid entries.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub line: usize,
+    pub addr: IpAddr,
+    pub hostname: String,
+    pub aliases: Vec<String>,
+}
+
+impl Entry {
+    // The canonical hostname followed by the aliases
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.hostname.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    InvalidUtf8,
+    InvalidAddress(String),
+    InvalidHostname(String),
+    MissingHostname,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineError {
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ErrorKind::InvalidUtf8 => write!(f, "not valid UTF-8"),
+            ErrorKind::InvalidAddress(addr) => write!(f, "invalid IP address `{}`", addr),
+            ErrorKind::InvalidHostname(name) => write!(f, "invalid hostname `{}`", name),
+            ErrorKind::MissingHostname => write!(f, "address without a hostname"),
+        }
+    }
+}
+
+// A hostname that maps to different addresses of the same family. Only
+// the first one is used by most resolvers, so the later ones are likely
+// a mistake. `localhost` on both 127.0.0.1 and ::1 is not reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    pub hostname: String,
+    pub first: (usize, IpAddr),
+    pub other: (usize, IpAddr),
+}
+
+impl fmt::Display for Duplicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}: `{}` maps to {}, but line {} already maps it to {}",
+            self.other.0, self.hostname, self.other.1, self.first.0, self.first.1
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Hosts {
+    entries: Vec<Entry>,
+    errors: Vec<LineError>,
+    duplicates: Vec<Duplicate>,
+    // Lowercased hostname to indices into `entries`, in file order
+    by_name: HashMap<String, Vec<usize>>,
+    by_addr: HashMap<IpAddr, Vec<usize>>,
+}
+
+impl Hosts {
+    // Only I/O errors fail the whole parse, malformed lines end up in
+    // `errors()`
+    pub fn parse<R: BufRead>(mut reader: R) -> io::Result<Hosts> {
+        let mut hosts = Hosts::default();
+        let mut buf = Vec::new();
+        let mut line = 0;
+
+        loop {
+            buf.clear();
+            if reader.read_until(b'\n', &mut buf)? == 0 {
+                break;
+            }
+            line += 1;
+
+            match std::str::from_utf8(&buf) {
+                Ok(text) => match parse_line(line, text) {
+                    Ok(Some(entry)) => hosts.insert(entry),
+                    Ok(None) => {}
+                    Err(kind) => hosts.errors.push(LineError { line, kind }),
+                },
+                Err(_) => hosts.errors.push(LineError {
+                    line,
+                    kind: ErrorKind::InvalidUtf8,
+                }),
+            }
+        }
+
+        Ok(hosts)
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn errors(&self) -> &[LineError] {
+        &self.errors
+    }
+
+    pub fn duplicates(&self) -> &[Duplicate] {
+        &self.duplicates
+    }
+
+    // Forward lookup, case-insensitive. Addresses come in file order, so
+    // the first one is what a resolver would return.
+    pub fn lookup(&self, hostname: &str) -> Vec<IpAddr> {
+        let mut addrs: Vec<IpAddr> = Vec::new();
+        for &i in self
+            .by_name
+            .get(&hostname.to_ascii_lowercase())
+            .into_iter()
+            .flatten()
+        {
+            if !addrs.contains(&self.entries[i].addr) {
+                addrs.push(self.entries[i].addr);
+            }
+        }
+        addrs
+    }
+
+    // Reverse lookup: every name, canonical ones first, for `addr`
+    pub fn reverse(&self, addr: IpAddr) -> Vec<&str> {
+        let entries = self.by_addr.get(&addr).into_iter().flatten();
+        let entries: Vec<&Entry> = entries.map(|&i| &self.entries[i]).collect();
+
+        let mut names: Vec<&str> = entries.iter().map(|e| e.hostname.as_str()).collect();
+        for entry in entries {
+            names.extend(entry.aliases.iter().map(String::as_str));
+        }
+        let mut seen = Vec::new();
+        names.retain(|name| {
+            let fresh = !seen.contains(name);
+            seen.push(*name);
+            fresh
+        });
+        names
+    }
+
+    fn insert(&mut self, entry: Entry) {
+        let index = self.entries.len();
+
+        for name in entry.names() {
+            let key = name.to_ascii_lowercase();
+            let previous = self.by_name.entry(key).or_default();
+
+            let conflict = previous
+                .iter()
+                .map(|&i| &self.entries[i])
+                .find(|e| e.addr != entry.addr && e.addr.is_ipv4() == entry.addr.is_ipv4());
+            if let Some(first) = conflict {
+                self.duplicates.push(Duplicate {
+                    hostname: name.to_string(),
+                    first: (first.line, first.addr),
+                    other: (entry.line, entry.addr),
+                });
+            }
+
+            if !previous.contains(&index) {
+                previous.push(index);
+            }
+        }
+
+        self.by_addr.entry(entry.addr).or_default().push(index);
+        self.entries.push(entry);
+    }
+}
+
+// `Ok(None)` for blank and comment-only lines
+fn parse_line(line: usize, text: &str) -> Result<Option<Entry>, ErrorKind> {
+    let text = match text.find('#') {
+        Some(comment) => &text[..comment],
+        None => text,
+    };
+    let mut fields = text.split_whitespace();
+
+    let addr = match fields.next() {
+        Some(addr) => addr,
+        None => return Ok(None),
+    };
+    // Link-local IPv6 addresses may carry a zone, as in `fe80::1%lo0`.
+    // The zone only matters to the kernel, so it is dropped.
+    let ip = addr.split('%').next().unwrap_or(addr);
+    let addr: IpAddr = ip
+        .parse()
+        .map_err(|_| ErrorKind::InvalidAddress(addr.to_string()))?;
+
+    let mut names = Vec::new();
+    for name in fields {
+        if !is_valid_hostname(name) {
+            return Err(ErrorKind::InvalidHostname(name.to_string()));
+        }
+        names.push(name.to_string());
+    }
+    if names.is_empty() {
+        return Err(ErrorKind::MissingHostname);
+    }
+    let hostname = names.remove(0);
+
+    Ok(Some(Entry {
+        line,
+        addr,
+        hostname,
+        aliases: names,
+    }))
+}
+
+// RFC 1123: dot-separated labels of letters, digits and hyphens, at most
+// 63 characters each and 253 in total, not starting or ending with a
+// hyphen. A trailing dot for a fully qualified name is accepted.
+fn is_valid_hostname(name: &str) -> bool {
+    let name = name.strip_suffix('.').unwrap_or(name);
+
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
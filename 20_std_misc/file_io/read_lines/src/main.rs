@@ -1,5 +1,9 @@
+mod hosts;
+
+use hosts::Hosts;
 use std::fs::File;
 use std::io::{self, BufRead};
+use std::net::IpAddr;
 use std::path::Path;
 
 // $ echo -e "127.0.0.1\n192.168.0.1\n" > hosts
@@ -11,12 +15,39 @@ fn main() {
     // File hosts must exist in current path before this produces output
     if let Ok(lines) = read_lines("./hosts") {
         // Consumes the iterator, returns an (Optional) String
-        for line in lines {
-            if let Ok(ip) = line {
-                println!("{}", ip);
+        for (number, line) in lines.enumerate() {
+            match line {
+                Ok(line) => println!("{}", line),
+                // A line that isn't valid UTF-8 is reported, not skipped
+                Err(why) => eprintln!("line {}: {}", number + 1, why),
             }
         }
     }
+
+    // Parse the same file as a hosts file
+    let hosts = match File::open("./hosts").and_then(|f| Hosts::parse(io::BufReader::new(f))) {
+        Err(why) => panic!("couldn't read ./hosts: {}", why),
+        Ok(hosts) => hosts,
+    };
+
+    for entry in hosts.entries() {
+        println!(
+            "{} -> {} (aliases: {:?})",
+            entry.addr, entry.hostname, entry.aliases
+        );
+    }
+    for error in hosts.errors() {
+        eprintln!("error: {}", error);
+    }
+    for duplicate in hosts.duplicates() {
+        eprintln!("warning: {}", duplicate);
+    }
+
+    // Forward lookups ignore case, reverse lookups list every name
+    println!("localhost resolves to {:?}", hosts.lookup("localhost"));
+    println!("nas.lan resolves to {:?}", hosts.lookup("nas.lan"));
+    let router: IpAddr = "192.168.0.1".parse().unwrap();
+    println!("{} is known as {:?}", router, hosts.reverse(router));
 }
 
 // The output is wrapped in a Result to allow matching on errors
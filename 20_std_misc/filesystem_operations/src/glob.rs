@@ -0,0 +1,143 @@
+// Shell-style glob patterns, matched against `/`-separated relative paths.
+//
+// * `?` matches one character other than `/`
+// * `*` matches any run of characters other than `/`
+// * `**` matches anything, `/` included, and `**/` also matches nothing,
+//   so `src/**/*.rs` matches both `src/main.rs` and `src/a/b/lib.rs`
+// * `[abc]`, `[a-z]` and `[!a-z]` match one character from, or not from, a set
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Char(char),
+    Any,
+    Star,
+    // `**` followed by `/`, may match the empty string
+    AnyDirs,
+    // `**` anywhere else
+    AnyPath,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    source: String,
+    tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub pattern: String,
+    pub message: &'static str,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid glob `{}`: {}", self.pattern, self.message)
+    }
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Pattern, PatternError> {
+        let err = |message| PatternError {
+            pattern: pattern.to_string(),
+            message,
+        };
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '?' => tokens.push(Token::Any),
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    i += 1;
+                    if chars.get(i + 1) == Some(&'/') {
+                        i += 1;
+                        tokens.push(Token::AnyDirs);
+                    } else {
+                        tokens.push(Token::AnyPath);
+                    }
+                }
+                '*' => tokens.push(Token::Star),
+                '[' => {
+                    let negated = chars.get(i + 1) == Some(&'!');
+                    let mut j = if negated { i + 2 } else { i + 1 };
+                    let mut ranges = Vec::new();
+                    // A `]` right after the opening bracket is literal
+                    let start = j;
+                    while j < chars.len() && (chars[j] != ']' || j == start) {
+                        if chars.get(j + 1) == Some(&'-')
+                            && chars.get(j + 2).is_some_and(|&c| c != ']')
+                        {
+                            ranges.push((chars[j], chars[j + 2]));
+                            j += 3;
+                        } else {
+                            ranges.push((chars[j], chars[j]));
+                            j += 1;
+                        }
+                    }
+                    if j >= chars.len() {
+                        return Err(err("unclosed `[`"));
+                    }
+                    tokens.push(Token::Class { negated, ranges });
+                    i = j;
+                }
+                c => tokens.push(Token::Char(c)),
+            }
+            i += 1;
+        }
+
+        Ok(Pattern {
+            source: pattern.to_string(),
+            tokens,
+        })
+    }
+
+    // A pattern without a `/` is matched against the last component only,
+    // as in `.gitignore`: `*.txt` matches `a/c/e.txt`
+    pub fn matches_path(&self, path: &str) -> bool {
+        if self.source.contains('/') {
+            self.matches(path)
+        } else {
+            self.matches(path.rsplit('/').next().unwrap_or(path))
+        }
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        match_tokens(&self.tokens, &text)
+    }
+}
+
+fn match_tokens(tokens: &[Token], text: &[char]) -> bool {
+    let (token, rest) = match tokens.split_first() {
+        Some(split) => split,
+        None => return text.is_empty(),
+    };
+
+    match token {
+        Token::Char(c) => text.first() == Some(c) && match_tokens(rest, &text[1..]),
+        Token::Any => text.first().is_some_and(|&c| c != '/') && match_tokens(rest, &text[1..]),
+        Token::Class { negated, ranges } => {
+            text.first().is_some_and(|&c| {
+                c != '/' && ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+            }) && match_tokens(rest, &text[1..])
+        }
+        Token::Star => {
+            // Try every split point up to the next `/`
+            let limit = text.iter().position(|&c| c == '/').unwrap_or(text.len());
+            (0..=limit).any(|n| match_tokens(rest, &text[n..]))
+        }
+        Token::AnyPath => (0..=text.len()).any(|n| match_tokens(rest, &text[n..])),
+        Token::AnyDirs => {
+            // Zero directories, or any prefix that ends in a `/`
+            match_tokens(rest, text)
+                || (0..text.len()).any(|n| text[n] == '/' && match_tokens(rest, &text[n + 1..]))
+        }
+    }
+}
@@ -1,11 +1,22 @@
 // The `std::fs` module contains several functions that deal with the filesystem.
 
+// The basic examples are kept as they were first written
+#![allow(
+    clippy::needless_borrow,
+    clippy::single_match,
+    clippy::suspicious_open_options
+)]
+
+mod glob;
+mod walk;
+
 use std::fs;
 use std::fs::{File, OpenOptions};
 use std::io;
 use std::io::prelude::*;
 use std::os::unix;
 use std::path::Path;
+use walk::WalkBuilder;
 
 // A simple implementation of `% cat path`
 fn cat(path: &Path) -> io::Result<String> {
@@ -82,6 +93,63 @@ fn main() {
         }
     }
 
+    println!("`ln -s .. a/c/d/up`");
+    // A link back to an ancestor, which a naive recursive walk would
+    // follow forever
+    if cfg!(target_family = "unix") {
+        unix::fs::symlink("..", "a/c/d/up").unwrap_or_else(|why| {
+            println!("! {:?}", why.kind());
+        });
+    }
+
+    println!("`find a -name '*.txt' -not -path 'a/c/d/*'`");
+    // Walk the whole tree, sorted, following symlinks, with glob filters.
+    // The exclude keeps this walk out of `a/c/d`, and so out of the loop
+    // through `a/c/d/up`; the next walk is the one that runs into it.
+    let walk = WalkBuilder::new("a")
+        .sort(true)
+        .follow_symlinks(true)
+        .include("*.txt")
+        .and_then(|w| w.exclude("c/d/*"))
+        .unwrap_or_else(|why| panic!("{}", why));
+    for entry in walk.build() {
+        match entry {
+            Err(why) => println!("! {}", why),
+            Ok(entry) => println!(
+                "> {:?} (depth {}, symlink: {})",
+                entry.path, entry.depth, entry.is_symlink
+            ),
+        }
+    }
+
+    println!("`find -L a -maxdepth 4`");
+    // Errors come out per entry, so the loop through `a/c/d/up` is
+    // reported and skipped rather than stopping the walk
+    for entry in WalkBuilder::new("a")
+        .sort(true)
+        .follow_symlinks(true)
+        .max_depth(4)
+        .build()
+    {
+        match entry {
+            Err(why) => println!("! {}", why),
+            Ok(entry) => println!(
+                "> {:?} ({})",
+                entry.path,
+                if entry.file_type.is_dir() {
+                    "dir"
+                } else {
+                    "file"
+                }
+            ),
+        }
+    }
+
+    println!("`rm a/c/d/up`");
+    fs::remove_file("a/c/d/up").unwrap_or_else(|why| {
+        println!("! {:?}", why.kind());
+    });
+
     println!("`rm a/c/e.txt`");
     // Remove a file, returns `io::Result<()>`
     fs::remove_file("a/c/e.txt").unwrap_or_else(|why| {
@@ -0,0 +1,288 @@
+// A recursive directory walker, roughly `find root -maxdepth n`.
+//
+// `Walk` is an iterator of `Result<Entry, Error>`. A directory that can't
+// be read produces one `Err` item and the walk carries on with its
+// siblings, so one bad directory never aborts the whole traversal.
+
+use crate::glob::{Pattern, PatternError};
+use std::fmt;
+use std::fs::{self, FileType, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::vec;
+
+#[derive(Debug)]
+pub struct Entry {
+    pub path: PathBuf,
+    // 0 for the root, 1 for its children and so on
+    pub depth: usize,
+    // The type of the symlink's target when symlinks are followed
+    pub file_type: FileType,
+    pub is_symlink: bool,
+}
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    Io(io::Error),
+    // A followed symlink leads back to the directory `ancestor`
+    Loop { ancestor: PathBuf },
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub path: PathBuf,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::Io(e) => write!(f, "{}: {}", self.path.display(), e),
+            ErrorKind::Loop { ancestor } => write!(
+                f,
+                "{}: symlink loop back to {}",
+                self.path.display(),
+                ancestor.display()
+            ),
+        }
+    }
+}
+
+pub struct WalkBuilder {
+    root: PathBuf,
+    max_depth: usize,
+    follow_symlinks: bool,
+    sort: bool,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl WalkBuilder {
+    pub fn new<P: AsRef<Path>>(root: P) -> WalkBuilder {
+        WalkBuilder {
+            root: root.as_ref().to_path_buf(),
+            max_depth: usize::MAX,
+            follow_symlinks: false,
+            sort: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+
+    // Entries deeper than `depth` are neither yielded nor read
+    pub fn max_depth(mut self, depth: usize) -> WalkBuilder {
+        self.max_depth = depth;
+        self
+    }
+
+    pub fn follow_symlinks(mut self, follow: bool) -> WalkBuilder {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    // Yields the entries of each directory sorted by file name instead of
+    // in the order the OS returns them
+    pub fn sort(mut self, sort: bool) -> WalkBuilder {
+        self.sort = sort;
+        self
+    }
+
+    // When any include pattern is given, only matching entries are
+    // yielded. Directories are still descended into either way.
+    pub fn include(mut self, pattern: &str) -> Result<WalkBuilder, PatternError> {
+        self.include.push(Pattern::new(pattern)?);
+        Ok(self)
+    }
+
+    // Matching entries are skipped, and matching directories are not
+    // descended into
+    pub fn exclude(mut self, pattern: &str) -> Result<WalkBuilder, PatternError> {
+        self.exclude.push(Pattern::new(pattern)?);
+        Ok(self)
+    }
+
+    pub fn build(self) -> Walk {
+        Walk {
+            stack: Vec::new(),
+            pending_root: Some(self.root.clone()),
+            pending_dir: None,
+            options: self,
+        }
+    }
+}
+
+// The identity of a directory, to notice when a symlink leads back into
+// one we are already inside of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DirId(u64, u64);
+
+#[cfg(unix)]
+fn dir_id(metadata: &Metadata) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    Some(DirId(metadata.dev(), metadata.ino()))
+}
+
+// Without inode numbers we can't tell directories apart cheaply, so loop
+// detection is only available on Unix
+#[cfg(not(unix))]
+fn dir_id(_metadata: &Metadata) -> Option<DirId> {
+    None
+}
+
+struct Frame {
+    entries: vec::IntoIter<Result<PathBuf, Error>>,
+    depth: usize,
+    dir: PathBuf,
+    id: Option<DirId>,
+}
+
+pub struct Walk {
+    options: WalkBuilder,
+    stack: Vec<Frame>,
+    pending_root: Option<PathBuf>,
+    // A directory that has been yielded and is read on the next call
+    pending_dir: Option<(PathBuf, usize, Option<DirId>)>,
+}
+
+impl Walk {
+    fn open_dir(&mut self, dir: PathBuf, depth: usize, id: Option<DirId>) -> Result<(), Error> {
+        let io_err = |path: &Path, e| Error {
+            path: path.to_path_buf(),
+            kind: ErrorKind::Io(e),
+        };
+
+        let mut paths = Vec::new();
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&dir).map_err(|e| io_err(&dir, e))? {
+            // An entry that can't be read is reported on its own, before
+            // the readable ones
+            match entry {
+                Ok(entry) => paths.push(entry.path()),
+                Err(e) => entries.push(Err(io_err(&dir, e))),
+            }
+        }
+        if self.options.sort {
+            paths.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+        }
+        entries.extend(paths.into_iter().map(Ok));
+
+        self.stack.push(Frame {
+            entries: entries.into_iter(),
+            depth,
+            dir,
+            id,
+        });
+        Ok(())
+    }
+
+    // Path relative to the root with `/` separators, for glob matching
+    fn relative(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.options.root).unwrap_or(path);
+        let parts: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        parts.join("/")
+    }
+
+    fn visit(&mut self, path: PathBuf, depth: usize) -> Option<Result<Entry, Error>> {
+        let relative = self.relative(&path);
+        if self
+            .options
+            .exclude
+            .iter()
+            .any(|p| p.matches_path(&relative))
+        {
+            return None;
+        }
+
+        let link_metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                return Some(Err(Error {
+                    path,
+                    kind: ErrorKind::Io(e),
+                }))
+            }
+        };
+        let is_symlink = link_metadata.file_type().is_symlink();
+
+        // A dangling symlink is reported as the link itself
+        let metadata = if is_symlink && self.options.follow_symlinks {
+            fs::metadata(&path).unwrap_or(link_metadata)
+        } else {
+            link_metadata
+        };
+        let file_type = metadata.file_type();
+
+        if file_type.is_dir() && depth < self.options.max_depth {
+            let id = dir_id(&metadata);
+            if is_symlink {
+                let ancestor = self
+                    .stack
+                    .iter()
+                    .find(|frame| frame.id.is_some() && frame.id == id);
+                if let Some(ancestor) = ancestor {
+                    return Some(Err(Error {
+                        path,
+                        kind: ErrorKind::Loop {
+                            ancestor: ancestor.dir.clone(),
+                        },
+                    }));
+                }
+            }
+            self.pending_dir = Some((path.clone(), depth + 1, id));
+        }
+
+        let included = self.options.include.is_empty()
+            || self
+                .options
+                .include
+                .iter()
+                .any(|p| p.matches_path(&relative));
+        if !included {
+            return None;
+        }
+
+        Some(Ok(Entry {
+            path,
+            depth,
+            file_type,
+            is_symlink,
+        }))
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Result<Entry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.pending_root.take() {
+            if let Some(item) = self.visit(root, 0) {
+                return Some(item);
+            }
+        }
+
+        loop {
+            if let Some((dir, depth, id)) = self.pending_dir.take() {
+                if let Err(e) = self.open_dir(dir, depth, id) {
+                    return Some(Err(e));
+                }
+            }
+
+            let frame = self.stack.last_mut()?;
+            let depth = frame.depth;
+            match frame.entries.next() {
+                Some(Ok(path)) => {
+                    if let Some(item) = self.visit(path, depth) {
+                        return Some(item);
+                    }
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+// Path manipulation that only looks at the path itself and never touches
+// the filesystem. Unlike `fs::canonicalize`, these functions work on paths
+// that don't exist, but they also can't see symlinks: `a/link/..` becomes
+// `a` even if `link` points somewhere else entirely.
+
+use std::path::{Component, Path, PathBuf};
+
+// Removes `.` components and resolves `..` against the preceding
+// component. A `..` that would climb above a root is dropped, one that
+// climbs above the start of a relative path is kept.
+//
+// `./a/b/../c/.` becomes `a/c`, `/../a` becomes `/a`, `../a/..` becomes `..`
+pub fn normalize<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut parts: Vec<Component> = Vec::new();
+
+    for component in path.as_ref().components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match parts.last() {
+                Some(Component::Normal(_)) => {
+                    parts.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                Some(Component::ParentDir) | Some(Component::CurDir) | None => {
+                    parts.push(component)
+                }
+            },
+            _ => parts.push(component),
+        }
+    }
+
+    if parts.is_empty() {
+        return PathBuf::from(".");
+    }
+    parts.iter().collect()
+}
+
+// The path that leads from `base` to `path`, so that
+// `normalize(base.join(relative_to(path, base)))` is `normalize(path)`.
+//
+// Returns `None` when there is no such path without looking at the
+// filesystem: when one path is absolute and the other isn't, or when
+// `base` climbs out through `..` further than `path` does.
+pub fn relative_to<P: AsRef<Path>, B: AsRef<Path>>(path: P, base: B) -> Option<PathBuf> {
+    let path = normalize(path);
+    let base = normalize(base);
+
+    if path.has_root() != base.has_root() {
+        return None;
+    }
+
+    let path: Vec<Component> = path
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+    let base: Vec<Component> = base
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+
+    // Different drives on Windows can't be bridged
+    if common == 0
+        && path
+            .first()
+            .is_some_and(|c| matches!(c, Component::Prefix(_)))
+    {
+        return None;
+    }
+
+    let mut relative = PathBuf::new();
+    for component in &base[common..] {
+        match component {
+            // Going up from `base` needs one `..` per directory...
+            Component::Normal(_) => relative.push(".."),
+            // ...but we can't know the name of the directory a `..` in
+            // `base` leads to, so we can't go back down through it
+            _ => return None,
+        }
+    }
+    for component in &path[common..] {
+        relative.push(component);
+    }
+
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    Some(relative)
+}
+
+// Whether `path`, taken relative to `root`, stays inside `root`. Useful to
+// vet untrusted paths such as archive entries or URL paths before joining
+// them onto a directory. Absolute paths are checked as they are.
+pub fn is_within<R: AsRef<Path>, P: AsRef<Path>>(root: R, path: P) -> bool {
+    let root = normalize(root);
+    let joined = normalize(root.join(path));
+
+    if root == Path::new(".") {
+        // Any relative path that doesn't start by climbing out is inside
+        return joined.is_relative() && !joined.starts_with("..");
+    }
+    joined.starts_with(&root)
+}
+
+// Splits a file name at its first dot rather than its last, so compound
+// extensions stay together: `myfile.tar.gz` gives `("myfile",
+// Some("tar.gz"))`. A leading dot belongs to the stem, as in `.bashrc` or
+// `.config.tar.gz`. Returns `None` if there is no file name or it isn't
+// valid UTF-8.
+pub fn split_extensions<P: AsRef<Path> + ?Sized>(path: &P) -> Option<(&str, Option<&str>)> {
+    let name = path.as_ref().file_name()?.to_str()?;
+
+    let (hidden, rest) = match name.strip_prefix('.') {
+        Some(rest) => (1, rest),
+        None => (0, name),
+    };
+
+    match rest.find('.') {
+        // A trailing dot, as in `file.`, isn't an extension
+        Some(dot) if dot + 1 < rest.len() => {
+            let split = hidden + dot;
+            Some((&name[..split], Some(&name[split + 1..])))
+        }
+        _ => Some((name, None)),
+    }
+}
+
+// Replaces the whole compound extension: `a/myfile.tar.gz` with `"zip"`
+// becomes `a/myfile.zip`. An empty extension removes it.
+pub fn with_extensions<P: AsRef<Path>>(path: P, extensions: &str) -> Option<PathBuf> {
+    let path = path.as_ref();
+    let (stem, _) = split_extensions(path)?;
+
+    let name = if extensions.is_empty() {
+        stem.to_string()
+    } else {
+        format!("{}.{}", stem, extensions)
+    };
+    Some(path.with_file_name(name))
+}
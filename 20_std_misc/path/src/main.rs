@@ -1,3 +1,5 @@
+mod lexical;
+
 use std::path::Path;
 
 fn main() {
@@ -15,6 +17,18 @@ fn main() {
     new_path.push("c");
     new_path.push("myfile.tar.gz");
 
+    // `extension` only sees the last extension, `split_extensions` keeps
+    // compound ones together
+    println!(
+        "extension: {:?}, compound: {:?}",
+        new_path.extension(),
+        lexical::split_extensions(&new_path)
+    );
+    println!(
+        "as a zip: {:?}",
+        lexical::with_extensions(&new_path, "zip").unwrap()
+    );
+
     // `set_file_name` updates the file name of the `PathBuf`
     new_path.set_file_name("package.tgz");
 
@@ -23,4 +37,25 @@ fn main() {
         None => panic!("new path is not a valid UTF-8 sequence"),
         Some(s) => println!("new path is {}", s),
     }
+
+    // The following never touch the filesystem, so none of these paths
+    // need to exist
+    println!("{:?}", lexical::normalize("./a/b/../c/./d.txt"));
+    println!("{:?}", lexical::normalize("/../etc"));
+    println!("{:?}", lexical::normalize("../a/.."));
+
+    println!(
+        "{:?}",
+        lexical::relative_to("/srv/www/img/logo.png", "/srv/www/css")
+    );
+    println!("{:?}", lexical::relative_to("a/b", "../c"));
+
+    // `is_within` catches paths that escape a root through `..`
+    for untrusted in ["img/logo.png", "img/../../etc/passwd", "/etc/passwd"] {
+        println!(
+            "{} stays inside /srv/www: {}",
+            untrusted,
+            lexical::is_within("/srv/www", untrusted)
+        );
+    }
 }
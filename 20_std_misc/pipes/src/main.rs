@@ -1,7 +1,10 @@
+mod pipeline;
+
+use pipeline::Pipeline;
 use std::io::prelude::*;
 use std::process::{Command, Stdio};
 
-static PANGRAM: &'static str = "the quick brown fox jumped over the lazy dog\n";
+static PANGRAM: &str = "the quick brown fox jumped over the lazy dog\n";
 
 fn main() {
     // Spawn the `wc` command
@@ -35,4 +38,59 @@ fn main() {
         Err(why) => panic!("couldn't read wc stdout: {}", why),
         Ok(_) => print!("wc responded with:\n{}", s),
     }
+
+    // The same idea with any number of stages:
+    // `echo $PANGRAM | tr ' ' '\n' | sort | uniq -c | sort -rn | head -n 3`
+    let mut words = Command::new("tr");
+    words.args([" ", "\n"]);
+    let mut count = Command::new("uniq");
+    count.arg("-c");
+    let mut by_count = Command::new("sort");
+    by_count.arg("-rn");
+    let mut top = Command::new("head");
+    top.args(["-n", "3"]);
+
+    let output = match Pipeline::new(words)
+        .pipe(Command::new("sort"))
+        .pipe(count)
+        .pipe(by_count)
+        .pipe(top)
+        .input_str(PANGRAM)
+        .run()
+    {
+        Err(why) => panic!("couldn't run pipeline: {}", why),
+        Ok(output) => output,
+    };
+    print!(
+        "most frequent words:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    // Input can also come from a file, which is handed to the first stage
+    // directly
+    let mut grep = Command::new("grep");
+    grep.arg("name");
+    let output = Pipeline::new(grep)
+        .pipe(Command::new("wc"))
+        .input_file("Cargo.toml")
+        .run()
+        .unwrap();
+    print!(
+        "wc of the `name` lines of Cargo.toml:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    // Like `set -o pipefail`, a failure in any stage fails the pipeline even
+    // though the last stage succeeded
+    let output = Pipeline::new(Command::new("false"))
+        .pipe(Command::new("cat"))
+        .run()
+        .unwrap();
+    println!(
+        "`false | cat` succeeded: {}, failed stage: {:?}, status: {}, all statuses: {:?}",
+        output.success(),
+        output.failed_stage(),
+        output.status(),
+        output.statuses
+    );
 }
@@ -0,0 +1,200 @@
+// A shell-style `a | b | c` pipeline.
+//
+// Each stage's stdout is handed straight to the next stage as its stdin,
+// so data flows from child to child through OS pipes and never passes
+// through this process. Only the input of the first stage and the output
+// of the last one are handled here.
+
+use std::fs::File;
+use std::io::{self, prelude::*};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+
+enum Input {
+    Null,
+    Bytes(Vec<u8>),
+    File(PathBuf),
+}
+
+pub struct Pipeline {
+    stages: Vec<Command>,
+    input: Input,
+}
+
+pub struct PipelineOutput {
+    // Exit status of every stage, in pipeline order
+    pub statuses: Vec<ExitStatus>,
+    // Everything the last stage wrote to stdout
+    pub stdout: Vec<u8>,
+}
+
+impl PipelineOutput {
+    // With `pipefail` semantics the pipeline fails if any stage fails, not
+    // just the last one
+    pub fn success(&self) -> bool {
+        self.statuses.iter().all(ExitStatus::success)
+    }
+
+    // The rightmost failing stage, whose status is the pipeline's status
+    pub fn failed_stage(&self) -> Option<usize> {
+        self.statuses.iter().rposition(|s| !s.success())
+    }
+
+    pub fn status(&self) -> ExitStatus {
+        let stage = self.failed_stage().unwrap_or(self.statuses.len() - 1);
+        self.statuses[stage]
+    }
+}
+
+impl Pipeline {
+    pub fn new(first: Command) -> Pipeline {
+        Pipeline {
+            stages: vec![first],
+            input: Input::Null,
+        }
+    }
+
+    pub fn pipe(mut self, next: Command) -> Pipeline {
+        self.stages.push(next);
+        self
+    }
+
+    pub fn input_str(mut self, input: &str) -> Pipeline {
+        self.input = Input::Bytes(input.as_bytes().to_vec());
+        self
+    }
+
+    // The file is opened by us and handed to the first stage as its stdin
+    pub fn input_file<P: Into<PathBuf>>(mut self, path: P) -> Pipeline {
+        self.input = Input::File(path.into());
+        self
+    }
+
+    // Takes the pipeline by value: a `Command` keeps the `Stdio`s it was
+    // given after spawning, so each stage is dropped as soon as it runs
+    pub fn run(self) -> io::Result<PipelineOutput> {
+        let mut children: Vec<Child> = Vec::with_capacity(self.stages.len());
+        let last = self.stages.len() - 1;
+
+        let mut stdin = match &self.input {
+            Input::Null => Stdio::null(),
+            Input::Bytes(_) => Stdio::piped(),
+            Input::File(path) => Stdio::from(File::open(path)?),
+        };
+
+        for (i, mut stage) in self.stages.into_iter().enumerate() {
+            let spawned = stage.stdin(stdin).stdout(Stdio::piped()).spawn();
+            drop(stage);
+
+            let mut child = match spawned {
+                Ok(child) => child,
+                Err(why) => {
+                    // Don't leave the earlier stages running
+                    kill_all(children);
+                    return Err(why);
+                }
+            };
+
+            // The read end of this stage's stdout becomes the next stage's
+            // stdin. Once the next stage has been spawned and dropped, we
+            // hold no copy of it, so EOF and SIGPIPE propagate as they do
+            // in a shell.
+            stdin = match child.stdout.take() {
+                Some(stdout) if i < last => Stdio::from(stdout),
+                Some(stdout) => {
+                    child.stdout = Some(stdout);
+                    Stdio::null()
+                }
+                None => Stdio::null(),
+            };
+            children.push(child);
+        }
+
+        // Feed the first stage from another thread while we read the output
+        // of the last one, otherwise both sides could fill their pipes
+        let writer = match (&self.input, children[0].stdin.take()) {
+            (Input::Bytes(bytes), Some(mut pipe)) => {
+                let bytes = bytes.clone();
+                Some(thread::spawn(move || match pipe.write_all(&bytes) {
+                    // A stage that stops reading early, like `head`, is fine
+                    Err(why) if why.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                    result => result,
+                }))
+            }
+            _ => None,
+        };
+
+        let mut stdout = Vec::new();
+        if let Some(mut out) = children[last].stdout.take() {
+            if let Err(why) = out.read_to_end(&mut stdout) {
+                // Killing the stages also makes the writer's pipe break,
+                // so it can be joined
+                kill_all(children);
+                if let Some(writer) = writer {
+                    let _ = writer.join();
+                }
+                return Err(why);
+            }
+        }
+
+        let statuses = children
+            .iter_mut()
+            .map(Child::wait)
+            .collect::<io::Result<Vec<_>>>()?;
+
+        if let Some(writer) = writer {
+            writer
+                .join()
+                .map_err(|_| io::Error::other("stdin writer panicked"))??;
+        }
+
+        Ok(PipelineOutput { statuses, stdout })
+    }
+}
+
+// Kills and reaps every stage, so none is left running or as a zombie
+fn kill_all(children: Vec<Child>) {
+    for mut child in children {
+        // Fails harmlessly for a stage that has already exited
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn upstream_stage_stops_when_downstream_exits() {
+        // `yes` only exits on SIGPIPE, which needs every read end of its
+        // stdout closed once `head` is done. Run it on another thread so a
+        // regression fails the test instead of hanging it.
+        let (done, result) = mpsc::channel();
+        thread::spawn(move || {
+            let mut head = Command::new("head");
+            head.arg("-n1");
+            let _ = done.send(Pipeline::new(Command::new("yes")).pipe(head).run());
+        });
+
+        let output = result
+            .recv_timeout(Duration::from_secs(10))
+            .expect("`yes | head -n1` didn't finish")
+            .unwrap();
+        assert_eq!(output.stdout, b"y\n");
+        assert!(output.statuses[1].success());
+    }
+
+    #[test]
+    fn pipefail() {
+        let output = Pipeline::new(Command::new("false"))
+            .pipe(Command::new("cat"))
+            .run()
+            .unwrap();
+        assert!(!output.success());
+        assert_eq!(output.failed_stage(), Some(0));
+    }
+}
@@ -3,6 +3,9 @@
 //
 // Those threads will be scheduled by the OS.
 
+mod parallel;
+
+use parallel::parallel_for_each;
 use std::thread;
 
 const NTHTHREADS: u32 = 10;
@@ -23,4 +26,23 @@ fn main() {
         // Wait for the thread to finish. Returns a result.
         let _ = child.join();
     }
+
+    // Scoped threads can borrow local data, so neither `move` nor a
+    // `'static` lifetime is needed. Results come back in input order.
+    let words = vec!["apple", "banana", "cherry", "date", "elderberry"];
+    let lengths = parallel_for_each(&words, 2, |word| word.len());
+    println!("lengths: {:?}", lengths);
+
+    // Every panic is collected, instead of only the first or none at all
+    let numbers: Vec<u32> = (0..NTHTHREADS).collect();
+    let halves = parallel_for_each(&numbers, 4, |&n| {
+        if n % 2 == 1 {
+            panic!("{} is odd", n);
+        }
+        n / 2
+    });
+    match halves {
+        Ok(halves) => println!("halves: {:?}", halves),
+        Err(why) => println!("{}", why),
+    }
 }
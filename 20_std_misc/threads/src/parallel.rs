@@ -0,0 +1,97 @@
+// A parallel `for_each` over a slice, built on scoped threads.
+//
+// `thread::scope` guarantees that every thread spawned inside it is joined
+// before `scope` returns, so the threads can borrow `items` and `f`
+// directly: no `move`, no `Arc` and no `'static` bound.
+
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicError {
+    // Index of every item whose call panicked, with the panic message, in
+    // input order
+    pub panics: Vec<(usize, String)>,
+}
+
+impl fmt::Display for PanicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} item(s) panicked:", self.panics.len())?;
+        for (index, message) in &self.panics {
+            write!(f, "\n  item {}: {}", index, message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PanicError {}
+
+// Calls `f` on every item using up to `n_threads` threads and returns the
+// results in the order of `items`. The items are split into contiguous
+// chunks whose sizes differ by at most one.
+//
+// A panic in `f` doesn't stop the other items. If any call panicked, every
+// panic is reported in the error and the successful results are dropped.
+pub fn parallel_for_each<T, R, F>(items: &[T], n_threads: usize, f: F) -> Result<Vec<R>, PanicError>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let n_threads = n_threads.clamp(1, items.len().max(1));
+    let (base, extra) = (items.len() / n_threads, items.len() % n_threads);
+    let f = &f;
+
+    let outcomes: Vec<Vec<Result<R, String>>> = thread::scope(|scope| {
+        let mut handles = Vec::with_capacity(n_threads);
+        let mut rest = items;
+
+        for i in 0..n_threads {
+            // The first `extra` chunks take one item more
+            let (chunk, tail) = rest.split_at(base + usize::from(i < extra));
+            rest = tail;
+
+            handles.push(scope.spawn(move || {
+                chunk
+                    .iter()
+                    .map(|item| {
+                        panic::catch_unwind(AssertUnwindSafe(|| f(item))).map_err(panic_message)
+                    })
+                    .collect()
+            }));
+        }
+
+        handles
+            .into_iter()
+            // Panics are caught per item, so the threads themselves finish
+            .map(|handle| handle.join().expect("worker thread panicked"))
+            .collect()
+    });
+
+    let mut results = Vec::with_capacity(items.len());
+    let mut panics = Vec::new();
+    for (index, outcome) in outcomes.into_iter().flatten().enumerate() {
+        match outcome {
+            Ok(result) => results.push(result),
+            Err(message) => panics.push((index, message)),
+        }
+    }
+
+    if panics.is_empty() {
+        Ok(results)
+    } else {
+        Err(PanicError { panics })
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
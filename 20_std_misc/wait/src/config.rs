@@ -0,0 +1,152 @@
+// The supervisor's config file, one INI-style section per program:
+//
+//     # comments start with `#`
+//     [worker]
+//     command = sh -c 'sleep 1; exit 1'
+//     restart = on-failure
+//     max_restarts = 5
+//
+// `command` is required and is run through `sh -c`. Every other key has
+// a default, see `ProgramSpec::new`.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    Always,
+    OnFailure,
+    Never,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProgramSpec {
+    pub name: String,
+    pub command: String,
+    pub restart: RestartPolicy,
+    // Delay before the first restart, doubled after every quick exit
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+    // At most `max_restarts` restarts within `restart_window`, after
+    // which the supervisor gives up on the program
+    pub max_restarts: usize,
+    pub restart_window: Duration,
+}
+
+impl ProgramSpec {
+    pub fn new(name: &str, command: &str) -> ProgramSpec {
+        ProgramSpec {
+            name: name.to_string(),
+            command: command.to_string(),
+            restart: RestartPolicy::OnFailure,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            max_restarts: 5,
+            restart_window: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "couldn't read config: {}", e),
+            ConfigError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<ProgramSpec>, ConfigError> {
+    parse(&fs::read_to_string(path)?)
+}
+
+pub fn parse(text: &str) -> Result<Vec<ProgramSpec>, ConfigError> {
+    // Each program along with the line its section starts on
+    let mut programs: Vec<(usize, ProgramSpec)> = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let err = |message: String| ConfigError::Parse {
+            line: line_no,
+            message,
+        };
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(err("empty program name".to_string()));
+            }
+            if programs.iter().any(|(_, p)| p.name == name) {
+                return Err(err(format!("program `{}` is defined twice", name)));
+            }
+            // The command is filled in, and checked, below
+            programs.push((line_no, ProgramSpec::new(name, "")));
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .map(|(k, v)| (k.trim(), v.trim()))
+            .ok_or_else(|| err(format!("expected `key = value`, found `{}`", line)))?;
+        let (_, program) = programs
+            .last_mut()
+            .ok_or_else(|| err(format!("`{}` appears before any [program] section", key)))?;
+
+        let number = |value: &str| -> Result<u64, ConfigError> {
+            value
+                .parse()
+                .map_err(|_| err(format!("`{}` expects a number, found `{}`", key, value)))
+        };
+
+        match key {
+            "command" => program.command = value.to_string(),
+            "restart" => {
+                program.restart = match value {
+                    "always" => RestartPolicy::Always,
+                    "on-failure" => RestartPolicy::OnFailure,
+                    "never" => RestartPolicy::Never,
+                    _ => {
+                        return Err(err(format!(
+                            "unknown restart policy `{}`, expected always, on-failure or never",
+                            value
+                        )))
+                    }
+                }
+            }
+            "backoff_ms" => program.backoff = Duration::from_millis(number(value)?),
+            "max_backoff_ms" => program.max_backoff = Duration::from_millis(number(value)?),
+            "max_restarts" => program.max_restarts = number(value)? as usize,
+            "restart_window_secs" => program.restart_window = Duration::from_secs(number(value)?),
+            _ => return Err(err(format!("unknown key `{}`", key))),
+        }
+    }
+
+    if let Some((line, program)) = programs.iter().find(|(_, p)| p.command.is_empty()) {
+        return Err(ConfigError::Parse {
+            line: *line,
+            message: format!("program `{}` has no command", program.name),
+        });
+    }
+
+    Ok(programs.into_iter().map(|(_, program)| program).collect())
+}
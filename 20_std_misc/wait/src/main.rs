@@ -1,4 +1,10 @@
-use std::process::Command;
+mod config;
+mod supervisor;
+
+use std::env;
+use std::process::{self, Command};
+
+use supervisor::Supervisor;
 
 // $ rustc main.rs && ./main
 // # `wait` keeps running for 5 seconds until the `sleep 5` command finishes
@@ -9,4 +15,24 @@ fn main() {
     let _result = child.wait().unwrap();
 
     println!("reached end of main");
+
+    // Rather than waiting on one child, supervise the programs listed in a
+    // config file, restarting them as they exit. Stop with SIGTERM or Ctrl-C.
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "supervisor.conf".to_string());
+    let specs = match config::load(&path) {
+        Ok(specs) => specs,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        }
+    };
+
+    let mut supervisor = Supervisor::new(specs);
+    for signal in [signal_hook::consts::SIGTERM, signal_hook::consts::SIGINT] {
+        signal_hook::flag::register(signal, supervisor.shutdown_flag())
+            .expect("couldn't install signal handler");
+    }
+    supervisor.run();
 }
@@ -0,0 +1,411 @@
+// A supervisor that keeps a set of child processes running.
+//
+// Instead of blocking in `Child::wait` on a single child, the supervisor
+// polls every child with `try_wait`. When a child exits, its restart
+// policy decides whether it is started again, after an exponentially
+// growing delay. A program that restarts too often within its window is
+// given up on. Once the shutdown flag is raised (by SIGTERM in `main`),
+// every child gets a SIGTERM of its own and, after a grace period, its
+// whole process group gets a SIGKILL, even if the child itself has exited
+// by then, so nothing it started is left behind.
+//
+// Each child runs in its own process group, so the signals also reach
+// whatever `sh -c` started, and a Ctrl-C in the terminal reaches only the
+// supervisor, which then stops the children in order.
+
+use crate::config::{ProgramSpec, RestartPolicy};
+use std::collections::VecDeque;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+// How long children get to exit after SIGTERM before they are killed
+const STOP_GRACE: Duration = Duration::from_secs(5);
+
+struct Program {
+    spec: ProgramSpec,
+    child: Option<Child>,
+    started_at: Instant,
+    // When to start the program next, `None` while it runs or after
+    // giving up
+    next_start: Option<Instant>,
+    backoff: Duration,
+    // Start times of recent restarts, for the rate limit
+    restarts: VecDeque<Instant>,
+}
+
+pub struct Supervisor {
+    programs: Vec<Program>,
+    shutdown: Arc<AtomicBool>,
+    stop_grace: Duration,
+}
+
+impl Supervisor {
+    pub fn new(specs: Vec<ProgramSpec>) -> Supervisor {
+        let now = Instant::now();
+        let programs = specs
+            .into_iter()
+            .map(|spec| Program {
+                backoff: spec.backoff,
+                spec,
+                child: None,
+                started_at: now,
+                next_start: Some(now),
+                restarts: VecDeque::new(),
+            })
+            .collect();
+
+        Supervisor {
+            programs,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            stop_grace: STOP_GRACE,
+        }
+    }
+
+    // Raising this flag, for example from a signal handler, makes `run`
+    // stop all children and return
+    pub fn shutdown_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.shutdown)
+    }
+
+    // Supervises until shutdown is requested or no program is left to run
+    pub fn run(&mut self) {
+        loop {
+            if self.shutdown.load(Ordering::SeqCst) {
+                log("supervisor", "shutdown requested, stopping children");
+                self.stop_all();
+                return;
+            }
+
+            let now = Instant::now();
+            for program in &mut self.programs {
+                if let Some(status) = program.reap() {
+                    program.schedule_restart(now, status.success());
+                }
+                if program.next_start.is_some_and(|at| at <= now) {
+                    program.start(now);
+                }
+            }
+
+            let active = self
+                .programs
+                .iter()
+                .any(|p| p.child.is_some() || p.next_start.is_some());
+            if !active {
+                log("supervisor", "no programs left to run");
+                return;
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn stop_all(&mut self) {
+        // The process groups to kill after the grace period, whose ids are
+        // the pids of the children
+        let mut groups = Vec::new();
+        for program in &mut self.programs {
+            program.next_start = None;
+            if let Some(child) = &program.child {
+                log(
+                    &program.spec.name,
+                    &format!("sending SIGTERM to pid {}", child.id()),
+                );
+                signal_group(child.id(), libc::SIGTERM);
+                groups.push(child.id());
+            }
+        }
+
+        let deadline = Instant::now() + self.stop_grace;
+        while Instant::now() < deadline && self.programs.iter().any(|p| p.child.is_some()) {
+            for program in &mut self.programs {
+                program.reap();
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        // Whatever is left of each group, including processes that
+        // outlived the child that started them. A group id isn't reused
+        // while any process is still in the group.
+        for pgid in groups {
+            signal_group(pgid, libc::SIGKILL);
+        }
+        for program in &mut self.programs {
+            if let Some(mut child) = program.child.take() {
+                log(&program.spec.name, "did not stop in time, killed");
+                match child.wait() {
+                    Ok(status) => log(&program.spec.name, &describe(status)),
+                    Err(e) => log(&program.spec.name, &format!("couldn't reap: {}", e)),
+                }
+            }
+        }
+    }
+}
+
+impl Program {
+    fn start(&mut self, now: Instant) {
+        self.next_start = None;
+        self.started_at = now;
+
+        let spawned = Command::new("sh")
+            .arg("-c")
+            .arg(&self.spec.command)
+            .process_group(0)
+            .spawn();
+
+        match spawned {
+            Ok(child) => {
+                log(&self.spec.name, &format!("started with pid {}", child.id()));
+                self.child = Some(child);
+            }
+            // Failing to spawn counts as a failed run
+            Err(e) => {
+                log(&self.spec.name, &format!("couldn't start: {}", e));
+                self.schedule_restart(now, false);
+            }
+        }
+    }
+
+    // Returns the child's exit status if it has exited since the last call
+    fn reap(&mut self) -> Option<ExitStatus> {
+        let status = match self.child.as_mut()?.try_wait() {
+            Ok(status) => status?,
+            Err(e) => {
+                log(&self.spec.name, &format!("couldn't check status: {}", e));
+                return None;
+            }
+        };
+
+        self.child = None;
+        log(&self.spec.name, &describe(status));
+        Some(status)
+    }
+
+    fn schedule_restart(&mut self, now: Instant, success: bool) {
+        let restart = match self.spec.restart {
+            RestartPolicy::Always => true,
+            RestartPolicy::OnFailure => !success,
+            RestartPolicy::Never => false,
+        };
+        if !restart {
+            log(&self.spec.name, "not restarting");
+            return;
+        }
+
+        // Forget restarts that have left the rate-limit window
+        while self
+            .restarts
+            .front()
+            .is_some_and(|&t| now.duration_since(t) > self.spec.restart_window)
+        {
+            self.restarts.pop_front();
+        }
+        if self.restarts.len() >= self.spec.max_restarts {
+            log(
+                &self.spec.name,
+                &format!(
+                    "restarted {} times within {:?}, giving up",
+                    self.restarts.len(),
+                    self.spec.restart_window
+                ),
+            );
+            return;
+        }
+
+        // A run that lasted longer than the longest backoff was healthy,
+        // so the next failure starts over from the initial delay
+        if now.duration_since(self.started_at) > self.spec.max_backoff {
+            self.backoff = self.spec.backoff;
+        }
+
+        let delay = self.backoff;
+        self.backoff = (self.backoff * 2).min(self.spec.max_backoff);
+        self.restarts.push_back(now);
+        self.next_start = Some(now + delay);
+        log(&self.spec.name, &format!("restarting in {:?}", delay));
+    }
+}
+
+// Sends `signal` to a child's whole process group, whose id is the
+// child's pid. Fails harmlessly once the group is empty.
+fn signal_group(pgid: u32, signal: libc::c_int) {
+    // SAFETY: `kill` has no memory-safety preconditions
+    unsafe {
+        libc::kill(-(pgid as libc::pid_t), signal);
+    }
+}
+
+fn describe(status: ExitStatus) -> String {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => format!("exited with code {}", code),
+        (None, Some(signal)) => format!("killed by signal {}", signal),
+        (None, None) => format!("exited with {}", status),
+    }
+}
+
+fn log(name: &str, message: &str) {
+    println!("{} [{}] {}", timestamp(), name, message);
+}
+
+// The current UTC time as `YYYY-MM-DDTHH:MM:SS.mmmZ`
+fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Converts days since 1970-01-01 to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60,
+        now.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // A file for the test's commands to write to, unique to the test
+    fn scratch(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("wait-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn quick(name: &str, command: &str) -> ProgramSpec {
+        ProgramSpec {
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(40),
+            ..ProgramSpec::new(name, command)
+        }
+    }
+
+    fn runs(path: &PathBuf) -> usize {
+        fs::read_to_string(path).map_or(0, |s| s.lines().count())
+    }
+
+    #[test]
+    fn on_failure_restarts_until_max_restarts() {
+        let log = scratch("failing");
+        let spec = ProgramSpec {
+            max_restarts: 3,
+            ..quick("failing", &format!("echo run >> {}; exit 1", log.display()))
+        };
+
+        // Returns by itself once it gives up
+        Supervisor::new(vec![spec]).run();
+        // The first run and three restarts
+        assert_eq!(runs(&log), 4);
+        let _ = fs::remove_file(log);
+    }
+
+    #[test]
+    fn on_failure_does_not_restart_success() {
+        let log = scratch("succeeding");
+        let spec = quick(
+            "succeeding",
+            &format!("echo run >> {}; exit 0", log.display()),
+        );
+
+        Supervisor::new(vec![spec]).run();
+        assert_eq!(runs(&log), 1);
+        let _ = fs::remove_file(log);
+    }
+
+    #[test]
+    fn never_does_not_restart_failure() {
+        let log = scratch("never");
+        let spec = ProgramSpec {
+            restart: RestartPolicy::Never,
+            ..quick("never", &format!("echo run >> {}; exit 1", log.display()))
+        };
+
+        Supervisor::new(vec![spec]).run();
+        assert_eq!(runs(&log), 1);
+        let _ = fs::remove_file(log);
+    }
+
+    #[test]
+    fn shutdown_flag_stops_running_children() {
+        let mut supervisor = Supervisor::new(vec![quick("sleeper", "sleep 30")]);
+        let flag = supervisor.shutdown_flag();
+        let raiser = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            flag.store(true, Ordering::SeqCst);
+        });
+
+        let start = Instant::now();
+        supervisor.run();
+        raiser.join().unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(supervisor.programs.iter().all(|p| p.child.is_none()));
+    }
+
+    #[test]
+    fn stop_kills_grandchildren_that_ignore_sigterm() {
+        let pid_file = scratch("grandchild");
+        // `sh` dies of the SIGTERM, the `sleep` it started ignores it
+        let command = format!(
+            "(trap '' TERM; exec sleep 30) & echo $! > {}; wait",
+            pid_file.display()
+        );
+        let mut supervisor = Supervisor::new(vec![quick("parent", &command)]);
+        supervisor.stop_grace = Duration::from_millis(200);
+
+        // Start it, then wait for the grandchild to be up
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let now = Instant::now();
+        supervisor.programs[0].start(now);
+        let pid = loop {
+            if let Ok(pid) = fs::read_to_string(&pid_file) {
+                if let Ok(pid) = pid.trim().parse::<u32>() {
+                    break pid;
+                }
+            }
+            assert!(Instant::now() < deadline, "grandchild didn't start");
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        supervisor.stop_all();
+        // Gone, or a zombie waiting for init to reap it, once the SIGKILL
+        // has been delivered
+        let state = || {
+            let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap_or_default();
+            stat.rsplit(')')
+                .next()
+                .and_then(|s| s.split_whitespace().next())
+                .map(str::to_string)
+        };
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while !matches!(state().as_deref(), None | Some("Z")) {
+            assert!(Instant::now() < deadline, "grandchild state {:?}", state());
+            thread::sleep(POLL_INTERVAL);
+        }
+        let _ = fs::remove_file(pid_file);
+    }
+}
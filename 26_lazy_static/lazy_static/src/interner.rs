@@ -0,0 +1,109 @@
+// A process-wide string interner. Every distinct string is stored exactly
+// once and handed out as a small `Copy` `Symbol`, so comparing two
+// identifiers is a single integer comparison instead of a string compare.
+//
+// The table lives in a `lazy_static!` so it is created on first use, and
+// is guarded by a `RwLock` so that lookups of already interned strings
+// from many threads only ever take the shared lock.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn as_str(self) -> &'static str {
+        resolve(self)
+    }
+
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    // Number of distinct strings in the table
+    pub symbols: usize,
+    // Total length in bytes of all distinct strings
+    pub bytes: usize,
+    // Calls to `intern` that found the string already present
+    pub hits: usize,
+    // Calls to `intern` that had to add a new string
+    pub misses: usize,
+}
+
+#[derive(Default)]
+struct Interner {
+    map: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+    bytes: usize,
+}
+
+lazy_static! {
+    static ref INTERNER: RwLock<Interner> = RwLock::new(Interner::default());
+}
+
+// The counters are bumped on the read path too, so they live outside the
+// lock in plain atomics.
+static HITS: AtomicUsize = AtomicUsize::new(0);
+static MISSES: AtomicUsize = AtomicUsize::new(0);
+
+pub fn intern(s: &str) -> Symbol {
+    // Fast path: the string is already known, a shared lock is enough
+    if let Some(&sym) = INTERNER.read().unwrap().map.get(s) {
+        HITS.fetch_add(1, Ordering::Relaxed);
+        return sym;
+    }
+
+    let mut interner = INTERNER.write().unwrap();
+
+    // Another thread may have interned the same string while we were
+    // waiting for the exclusive lock
+    if let Some(&sym) = interner.map.get(s) {
+        HITS.fetch_add(1, Ordering::Relaxed);
+        return sym;
+    }
+
+    // Interned strings are never freed, leaking them is what lets
+    // `resolve` hand out `&'static str`
+    let s: &'static str = Box::leak(s.to_owned().into_boxed_str());
+    let sym = Symbol(u32::try_from(interner.strings.len()).expect("interner is full"));
+
+    interner.map.insert(s, sym);
+    interner.strings.push(s);
+    interner.bytes += s.len();
+    MISSES.fetch_add(1, Ordering::Relaxed);
+
+    sym
+}
+
+pub fn resolve(sym: Symbol) -> &'static str {
+    // A `Symbol` can only be created by `intern`, so the index is always valid
+    INTERNER.read().unwrap().strings[sym.0 as usize]
+}
+
+// Returns the symbol for `s` without interning it
+pub fn lookup(s: &str) -> Option<Symbol> {
+    INTERNER.read().unwrap().map.get(s).copied()
+}
+
+pub fn stats() -> Stats {
+    let interner = INTERNER.read().unwrap();
+
+    Stats {
+        symbols: interner.strings.len(),
+        bytes: interner.bytes,
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+    }
+}
@@ -4,7 +4,11 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod interner;
+
+use interner::Symbol;
 use std::collections::HashMap;
+use std::thread;
 
 lazy_static! {
     static ref HASHMAP: HashMap<u32, &'static str> = {
@@ -29,4 +33,27 @@ fn main() {
         "A expensive calculation on a static results in: {}.",
         *NUMBER
     );
+
+    // The interner is itself a lazily initialized static. Strings built at
+    // runtime can't be `&'static str` keys like those in `HASHMAP`, but
+    // their symbols can.
+    let name = String::from("foo");
+    let sym = interner::intern(&name);
+    assert_eq!(sym, interner::intern("foo"));
+    assert_ne!(sym, interner::intern("bar"));
+    println!("`{}` was interned as symbol #{}.", sym, sym.as_u32());
+
+    // Interning the same identifiers from several threads yields the same
+    // symbols everywhere
+    let handles: Vec<_> = (0..4)
+        .map(|_| thread::spawn(|| ["foo", "bar", "baz"].map(interner::intern)))
+        .collect();
+    for handle in handles {
+        let symbols: [Symbol; 3] = handle.join().unwrap();
+        assert_eq!(symbols[0], sym);
+    }
+
+    assert_eq!(interner::lookup("baz").map(Symbol::as_str), Some("baz"));
+    assert_eq!(interner::lookup("qux"), None);
+    println!("Interner stats: {:?}", interner::stats());
 }